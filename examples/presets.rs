@@ -0,0 +1,43 @@
+// Prints the presets of `rk_fall::presets` for the mint script. Without arguments it lists
+// them, and given a name it prints that preset as the bash arrays mint.sh mints from:
+//
+//     cargo run --quiet --example presets -- "figure of eight"
+
+use rk_fall::int_rk4::BodyKind;
+use rk_fall::presets::{preset, presets};
+
+use std::process::exit;
+
+fn array<T: ToString>(values: impl Iterator<Item = T>) -> String {
+    values.map(|v| v.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+fn main() {
+    let Some(name) = std::env::args().nth(1) else {
+        for preset in presets() {
+            println!("{} ({} ticks)", preset.name, preset.ticks);
+        }
+        return;
+    };
+    let Some(preset) = preset(&name) else {
+        eprintln!("unknown preset {}", name);
+        exit(1);
+    };
+
+    // kinds are left empty when all bodies are free, as tokens minted before kinds were
+    let system = &preset.system;
+    if system.iter().all(|s| s.get_kind() == BodyKind::Free) {
+        println!("kind=()");
+    } else {
+        println!(
+            "kind=({})",
+            array(system.iter().map(|s| u8::from(s.get_kind())))
+        );
+    }
+    println!("mass=({})", array(system.iter().map(|s| s.get_mass())));
+    println!("x=({})", array(system.iter().map(|s| s.get_x())));
+    println!("y=({})", array(system.iter().map(|s| s.get_y())));
+    println!("vel_x=({})", array(system.iter().map(|s| s.get_vel_x())));
+    println!("vel_y=({})", array(system.iter().map(|s| s.get_vel_y())));
+    println!("ticks={}", preset.ticks);
+}
@@ -0,0 +1,8 @@
+# checks that every target steps the mint presets to the same states, see
+# tests/conformance.rs. As in test.sh, main.rs is swapped out for the tests
+
+sed -i 's/\#\!\[cfg_attr(all(not(feature = \"export-abi\")), no_main, no_std)\]/fn main() {}/' src/main.rs
+cargo test --test conformance && wasm-pack test --node -- --test conformance
+status=$?
+sed -i 's/fn main() {}/#\!\[cfg_attr(all(not(feature = \"export-abi\")), no_main, no_std)\]/' src/main.rs
+exit $status
@@ -1,54 +1,69 @@
 #!/bin/bash
 
-# simple orbit
-# mass=(10000 1)
-# x=(0 0)
-# y=(0 10000)
-# vel_x=(0 10000)
-# vel_y=(0 0)
-# ticks=1000
-
-# our separate ways
-mass=(100000000 100000001 100000002)
-x=(0 -100000000 100000000)
-y=(100000000 -100000000 -100000000)
-vel_x=(30000000 0 -30000000)
-vel_y=(0 30000000 0)
-ticks=4000
-
-# slow dance
-# mass=(100000000 100000001 100000002)
-# x=(50000000 -70000000 -20000000)
-# y=(50000000 -50000000 60000000)
-# vel_x=(30000000 0 0)
-# vel_y=(0 30000000 -30000000)
-# ticks=8000
-
-# figure of eight
-# mass=(100000000 100000001 100000002)
-# x=(-97000436 0 97000436)
-# y=(24208753 0 -24208753)
-# vel_x=(46620368 -93324973 46620368)
-# vel_y=(43236573 -86473146 43236573)
-# ticks=4000
-
-# double orbit
-# mass=(40000 40001)
-# x=(0 0)
-# y=(10000 -10000)
-# vel_x=(10000 -10000)
-# vel_y=(0 0)
-# ticks=4000
+# a preset from src/presets.rs, one of those listed by `cargo run --quiet --example presets`.
+# Leave it empty to mint the system of the arrays below instead
+preset="our separate ways"
+
+# kind of each body: 0 free, 1 an anchor that pulls but never moves and 2 a tracer that
+# is pulled but pulls nothing. Empty for free bodies only
+kind=()
+mass=()
+x=()
+y=()
+vel_x=()
+vel_y=()
+ticks=0
+
+# integration scheme: 0 is the original per-body rk4, 1 is the coupled rk4
+# 2 euler, 3 heun, 4 rk3, 5 rk4 3/8-rule, 6 leapfrog, 7 velocity verlet, 8 yoshida-4
+scheme=0
+
+# gravitational softening length, 0 for plain gravity. Close bodies attract as if they
+# were at least this far apart
+softening=0
+
+# decimal places all of the values above are given in: 6, 8, 10 or 12. The values
+# above assume 8, so add or remove zeros to match
+decimals=8
+
+# gravitational constant and time step of each tick, at the scale of decimals. A gravity
+# of 1 and a step of 0.001, here 100000000 and 100000, are what tokens have always used
+grav_g=100000000
+time_period_sec=100000
+
+# force law the bodies move under, empty for gravity. Otherwise the bytes of an int_rk4
+# ForceLaw, for example [1,3] for gravity falling off as the cube of the distance
+force=[]
+
+# events logged as the bodies move: pairs closer than close_approach, at the scale of
+# decimals and 0 for none, and with escapes and apsides bodies breaking free and passing
+# the nearest and farthest points of their orbits. They leave the token id as it is
+close_approach=0
+escapes=false
+apsides=false
+
+# move the system into the frame of its centre of mass before minting, so that it stays
+# in view rather than drifting off. Centred tokens have their own token ids
+centre=false
 
 address=$1
 
+if [ -n "$preset" ]; then
+    arrays=$(cargo run --quiet --example presets -- "$preset") || exit 1
+    eval "$arrays"
+fi
+
+kind_string="["$(IFS=, ; echo "${kind[*]}")"]"
 mass_string="["$(IFS=, ; echo "${mass[*]}")"]"
 x_string="["$(IFS=, ; echo "${x[*]}")"]"
 y_string="["$(IFS=, ; echo "${y[*]}")"]"
 vel_x_string="["$(IFS=, ; echo "${vel_x[*]}")"]"
 vel_y_string="["$(IFS=, ; echo "${vel_y[*]}")"]"
 
-cast send --gas-limit 12000000 --private-key $PRIV_KEY --rpc-url $RPC_URL $address "mint(uint64[],int64[],int64[],int64[],int64[],uint32)" $mass_string $x_string $y_string $vel_x_string $vel_y_string $ticks
+options="($decimals,$scheme,$grav_g,$time_period_sec,$softening,$force,$kind_string,$close_approach,$escapes,$apsides,$centre)"
+bodies="($mass_string,$x_string,$y_string,$vel_x_string,$vel_y_string)"
+
+cast send --gas-limit 12000000 --private-key $PRIV_KEY --rpc-url $RPC_URL $address "mintWithOptions((uint8,uint8,int64,int64,int64,uint8[],uint8[],int64,bool,bool,bool),(uint64[],int64[],int64[],int64[],int64[]),uint32)" "$options" "$bodies" $ticks
 
 # some possible errors
 # 0x7e273289 ERC721NonexistentToken(uint256 tokenId);
@@ -58,4 +73,12 @@ cast send --gas-limit 12000000 --private-key $PRIV_KEY --rpc-url $RPC_URL $addre
 # 0x177e802f ERC721InsufficientApproval(address operator, uint256 tokenId)
 # 0xa9fbf51f ERC721InvalidApprover(address approver)
 # 0x5b08ba18 ERC721InvalidOperator(address operator)
-
+# 0x3930a25f AlreadyMinted(uint256 tokenId, address owner)
+# 0x463040dc UnknownScheme(uint8 scheme)
+# 0x5164cb64 MismatchedLengths(uint256 bodies, uint256 length)
+# 0x35278d12 Overflow()
+# 0x992acb5b ZeroDistance()
+# 0x80b6feae NegativeSqrt()
+# 0x2bb5814f UnsupportedScale(uint8 decimals)
+# 0x3dc9ec34 UnknownForce()
+# 0xbd3c9089 UnknownKind(uint8 kind)
@@ -0,0 +1,182 @@
+use crate::int_rk4::{isqrt, BodyKind, FixedPoint, MotionState, PhysicsParams, SimError};
+
+use serde::{Deserialize, Serialize};
+
+// Conserved quantities of a system, used to judge how well it was integrated. Like
+// the states themselves all values are scaled by the scale of the system, but are kept
+// in i128 since products of masses, positions and velocities quickly overflow i64.
+// Gravity and softening are those of the run, given as `PhysicsParams` at the same
+// scale, and a product too large even for i128 is an overflow. Kinds count as they do
+// in the forces: anchors never move, so they carry no kinetic energy or momentum, and
+// tracers pull nothing, so two tracers have no potential energy between them.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostics {
+    pub kinetic_energy: i128,
+    pub potential_energy: i128,
+    pub total_energy: i128,
+    pub momentum_x: i128,
+    pub momentum_y: i128,
+    /// Angular momentum about the origin.
+    pub angular_momentum: i128,
+}
+
+/// Relative change of each quantity between two systems, scaled by the scale of the
+/// systems. A quantity that starts at zero has no relative drift and is `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Drift {
+    pub total_energy: Option<i128>,
+    pub momentum_x: Option<i128>,
+    pub momentum_y: Option<i128>,
+    pub angular_momentum: Option<i128>,
+}
+
+fn checked(value: Option<i128>) -> Result<i128, SimError> {
+    value.ok_or(SimError::Overflow)
+}
+
+/// The bodies of `system` that are free to move, that is all but the anchors.
+fn moving<const SCALE: i64>(
+    system: &[MotionState<FixedPoint<SCALE>>],
+) -> impl Iterator<Item = &MotionState<FixedPoint<SCALE>>> {
+    system.iter().filter(|s| s.get_kind() != BodyKind::Anchor)
+}
+
+pub fn kinetic_energy<const SCALE: i64>(
+    system: &[MotionState<FixedPoint<SCALE>>],
+) -> Result<i128, SimError> {
+    let scale = SCALE as i128;
+
+    moving(system).try_fold(0, |energy: i128, s| {
+        let vel_x = s.get_vel_x() as i128;
+        let vel_y = s.get_vel_y() as i128;
+        let speed_squared = checked(
+            vel_x
+                .checked_mul(vel_x)
+                .zip(vel_y.checked_mul(vel_y))
+                .and_then(|(x, y)| x.checked_add(y)),
+        )?;
+        let twice = checked(speed_squared.checked_mul(s.get_mass() as i128))?;
+        checked(energy.checked_add(twice / (2 * scale * scale)))
+    })
+}
+
+/// The potential energy of every pair of bodies at least one of which pulls the other,
+/// at the distance softened as the gravity of the run is. Coincident bodies have an
+/// unbounded potential unless softened and are then left out.
+pub fn potential_energy<const SCALE: i64>(
+    params: &PhysicsParams,
+    system: &[MotionState<FixedPoint<SCALE>>],
+) -> Result<i128, SimError> {
+    let softening = params.softening as i128;
+    let mut energy: i128 = 0;
+
+    for (i, a) in system.iter().enumerate() {
+        for b in &system[i + 1..] {
+            if a.get_kind() == BodyKind::Tracer && b.get_kind() == BodyKind::Tracer {
+                continue;
+            }
+            let dx = a.get_x() as i128 - b.get_x() as i128;
+            let dy = a.get_y() as i128 - b.get_y() as i128;
+            let squared = checked(
+                dx.checked_mul(dx)
+                    .zip(dy.checked_mul(dy))
+                    .and_then(|(x, y)| x.checked_add(y))
+                    .and_then(|d| d.checked_add(softening * softening)),
+            )?;
+            let r = isqrt(squared as u128) as i128;
+            if r != 0 {
+                // G m_a m_b / r, scaled by SCALE like the rest
+                let pull = checked(
+                    (a.get_mass() as i128)
+                        .checked_mul(b.get_mass() as i128)
+                        .and_then(|m| m.checked_mul(params.grav_g as i128)),
+                )?;
+                energy = checked(energy.checked_sub(pull / (SCALE as i128 * r)))?;
+            }
+        }
+    }
+
+    Ok(energy)
+}
+
+pub fn momentum<const SCALE: i64>(
+    system: &[MotionState<FixedPoint<SCALE>>],
+) -> Result<(i128, i128), SimError> {
+    let scale = SCALE as i128;
+
+    moving(system).try_fold((0, 0), |(momentum_x, momentum_y): (i128, i128), s| {
+        let mass = s.get_mass() as i128;
+        let x = checked(mass.checked_mul(s.get_vel_x() as i128))? / scale;
+        let y = checked(mass.checked_mul(s.get_vel_y() as i128))? / scale;
+        Ok((
+            checked(momentum_x.checked_add(x))?,
+            checked(momentum_y.checked_add(y))?,
+        ))
+    })
+}
+
+pub fn angular_momentum<const SCALE: i64>(
+    system: &[MotionState<FixedPoint<SCALE>>],
+) -> Result<i128, SimError> {
+    let scale = SCALE as i128;
+
+    moving(system).try_fold(0, |total: i128, s| {
+        let moment = checked(
+            (s.get_x() as i128)
+                .checked_mul(s.get_vel_y() as i128)
+                .zip((s.get_y() as i128).checked_mul(s.get_vel_x() as i128))
+                .and_then(|(a, b)| a.checked_sub(b)),
+        )?;
+        let scaled = checked(moment.checked_mul(s.get_mass() as i128))? / (scale * scale);
+        checked(total.checked_add(scaled))
+    })
+}
+
+pub fn diagnostics<const SCALE: i64>(
+    params: &PhysicsParams,
+    system: &[MotionState<FixedPoint<SCALE>>],
+) -> Result<Diagnostics, SimError> {
+    let kinetic_energy = kinetic_energy(system)?;
+    let potential_energy = potential_energy(params, system)?;
+    let (momentum_x, momentum_y) = momentum(system)?;
+
+    Ok(Diagnostics {
+        kinetic_energy,
+        potential_energy,
+        total_energy: checked(kinetic_energy.checked_add(potential_energy))?,
+        momentum_x,
+        momentum_y,
+        angular_momentum: angular_momentum(system)?,
+    })
+}
+
+/// `(after - before) / |before|`, scaled by `scale`.
+pub fn relative_drift(scale: i64, before: i128, after: i128) -> Result<Option<i128>, SimError> {
+    if before == 0 {
+        return Ok(None);
+    }
+
+    let change = checked(
+        after
+            .checked_sub(before)
+            .and_then(|change| change.checked_mul(scale as i128)),
+    )?;
+    Ok(Some(change / checked(before.checked_abs())?))
+}
+
+pub fn drift<const SCALE: i64>(
+    params: &PhysicsParams,
+    before: &[MotionState<FixedPoint<SCALE>>],
+    after: &[MotionState<FixedPoint<SCALE>>],
+) -> Result<Drift, SimError> {
+    let before = diagnostics(params, before)?;
+    let after = diagnostics(params, after)?;
+
+    Ok(Drift {
+        total_energy: relative_drift(SCALE, before.total_energy, after.total_energy)?,
+        momentum_x: relative_drift(SCALE, before.momentum_x, after.momentum_x)?,
+        momentum_y: relative_drift(SCALE, before.momentum_y, after.momentum_y)?,
+        angular_momentum: relative_drift(SCALE, before.angular_momentum, after.angular_momentum)?,
+    })
+}
@@ -38,26 +38,26 @@ sol! {
 }
 
 pub enum Erc721Error {
-    ERC721NonexistentToken(ERC721NonexistentToken),
-    ERC721IncorrectOwner(ERC721IncorrectOwner),
-    ERC721InvalidSender(ERC721InvalidSender),
-    ERC721InvalidReceiver(ERC721InvalidReceiver),
-    ERC721InsufficientApproval(ERC721InsufficientApproval),
-    ERC721InvalidApprover(ERC721InvalidApprover),
-    ERC721InvalidOperator(ERC721InvalidOperator),
+    NonexistentToken(ERC721NonexistentToken),
+    IncorrectOwner(ERC721IncorrectOwner),
+    InvalidSender(ERC721InvalidSender),
+    InvalidReceiver(ERC721InvalidReceiver),
+    InsufficientApproval(ERC721InsufficientApproval),
+    InvalidApprover(ERC721InvalidApprover),
+    InvalidOperator(ERC721InvalidOperator),
 }
 
 // CHRIS: TODO: try out the prestwitch way of doing things with .sol
 impl From<Erc721Error> for Vec<u8> {
     fn from(err: Erc721Error) -> Vec<u8> {
         match err {
-            Erc721Error::ERC721NonexistentToken(e) => e.encode(),
-            Erc721Error::ERC721IncorrectOwner(e) => e.encode(),
-            Erc721Error::ERC721InvalidSender(e) => e.encode(),
-            Erc721Error::ERC721InvalidReceiver(e) => e.encode(),
-            Erc721Error::ERC721InsufficientApproval(e) => e.encode(),
-            Erc721Error::ERC721InvalidApprover(e) => e.encode(),
-            Erc721Error::ERC721InvalidOperator(e) => e.encode(),
+            Erc721Error::NonexistentToken(e) => e.encode(),
+            Erc721Error::IncorrectOwner(e) => e.encode(),
+            Erc721Error::InvalidSender(e) => e.encode(),
+            Erc721Error::InvalidReceiver(e) => e.encode(),
+            Erc721Error::InsufficientApproval(e) => e.encode(),
+            Erc721Error::InvalidApprover(e) => e.encode(),
+            Erc721Error::InvalidOperator(e) => e.encode(),
         }
     }
 }
@@ -77,7 +77,7 @@ impl<T: Erc721Params> Erc721<T> {
             let owner = self.owner_of(token_id)?;
 
             if auth != Address::ZERO && owner != auth && !self.is_approved_for_all(owner, auth)? {
-                return Err(Erc721Error::ERC721InvalidApprover(ERC721InvalidApprover {
+                return Err(Erc721Error::InvalidApprover(ERC721InvalidApprover {
                     approver: auth,
                 }));
             }
@@ -98,9 +98,9 @@ impl<T: Erc721Params> Erc721<T> {
 
     pub fn _require_minted(&self, token_id: U256) -> Erc721Result<()> {
         if self.owner_of(token_id)? == Address::ZERO {
-            return Err(Erc721Error::ERC721NonexistentToken(
-                ERC721NonexistentToken { tokenId: token_id },
-            ));
+            return Err(Erc721Error::NonexistentToken(ERC721NonexistentToken {
+                tokenId: token_id,
+            }));
         }
 
         Ok(())
@@ -126,11 +126,11 @@ impl<T: Erc721Params> Erc721<T> {
     ) -> Erc721Result<()> {
         if !self._is_authorized(owner, spender, token_id)? {
             if owner == Address::ZERO {
-                return Err(Erc721Error::ERC721NonexistentToken(
-                    ERC721NonexistentToken { tokenId: token_id },
-                ));
+                return Err(Erc721Error::NonexistentToken(ERC721NonexistentToken {
+                    tokenId: token_id,
+                }));
             } else {
-                return Err(Erc721Error::ERC721InsufficientApproval(
+                return Err(Erc721Error::InsufficientApproval(
                     ERC721InsufficientApproval {
                         operator: spender,
                         tokenId: token_id,
@@ -175,14 +175,14 @@ impl<T: Erc721Params> Erc721<T> {
 
     pub fn _mint(&mut self, to: Address, token_id: U256) -> Erc721Result<()> {
         if to == Address::ZERO {
-            return Err(Erc721Error::ERC721InvalidReceiver(ERC721InvalidReceiver {
+            return Err(Erc721Error::InvalidReceiver(ERC721InvalidReceiver {
                 receiver: Address::ZERO,
             }));
         }
 
         let prev_owner = self._update(to, token_id, Address::ZERO)?;
         if prev_owner != Address::ZERO {
-            return Err(Erc721Error::ERC721InvalidSender(ERC721InvalidSender {
+            return Err(Erc721Error::InvalidSender(ERC721InvalidSender {
                 sender: Address::ZERO,
             }));
         }
@@ -193,9 +193,9 @@ impl<T: Erc721Params> Erc721<T> {
     pub fn _burn(&mut self, token_id: U256) -> Erc721Result<()> {
         let prev_owner = self._update(Address::ZERO, token_id, Address::ZERO)?;
         if prev_owner == Address::ZERO {
-            return Err(Erc721Error::ERC721NonexistentToken(
-                ERC721NonexistentToken { tokenId: token_id },
-            ));
+            return Err(Erc721Error::NonexistentToken(ERC721NonexistentToken {
+                tokenId: token_id,
+            }));
         }
         Ok(())
     }
@@ -246,7 +246,7 @@ impl<T: Erc721Params> Erc721<T> {
 
     pub fn set_approval_for_all(&mut self, operator: Address, approved: bool) -> Erc721Result<()> {
         if operator == Address::ZERO {
-            return Err(Erc721Error::ERC721InvalidOperator(ERC721InvalidOperator {
+            return Err(Erc721Error::InvalidOperator(ERC721InvalidOperator {
                 operator: Address::ZERO,
             }));
         }
@@ -273,7 +273,7 @@ impl<T: Erc721Params> Erc721<T> {
         token_id: U256,
     ) -> Erc721Result<()> {
         if to == Address::ZERO {
-            return Err(Erc721Error::ERC721InvalidReceiver(ERC721InvalidReceiver {
+            return Err(Erc721Error::InvalidReceiver(ERC721InvalidReceiver {
                 receiver: Address::ZERO,
             }));
         }
@@ -281,7 +281,7 @@ impl<T: Erc721Params> Erc721<T> {
         let prev_owner = self._update(to, token_id, msg::sender())?;
 
         if prev_owner != from {
-            return Err(Erc721Error::ERC721IncorrectOwner(ERC721IncorrectOwner {
+            return Err(Erc721Error::IncorrectOwner(ERC721IncorrectOwner {
                 sender: from,
                 tokenId: token_id,
                 owner: prev_owner,
@@ -297,6 +297,6 @@ impl<T: Erc721Params> Erc721<T> {
         to: Address,
         token_id: U256,
     ) -> Erc721Result<()> {
-        Ok(self.transfer_from(from, to, token_id)?)
+        self.transfer_from(from, to, token_id)
     }
 }
@@ -1,174 +1,1171 @@
 use serde::{Deserialize, Serialize};
 
 use alloc::vec::Vec;
+
+mod adaptive;
+mod barnes_hut;
+mod checkpoint;
+mod collision;
+mod events;
+mod fixed;
+mod force;
+mod frame;
+mod kepler;
+mod math;
+mod scalar;
+mod symplectic;
+mod trajectory;
+mod trig;
+pub use adaptive::{
+    embedded_tableau, integrate_adaptive, tick_adaptive, AdaptiveRun, EmbeddedTableau, StepControl,
+    DORMAND_PRINCE, RKF45,
+};
+pub use barnes_hut::{tick_barnes_hut, tick_many_barnes_hut, BarnesHut, Tree};
+pub use checkpoint::{Checkpoint, CheckpointError, CHECKPOINT_VERSION};
+pub use collision::{
+    merge, merge_collisions, tick_many_merging, tick_merging, Collision, CollisionRun,
+};
+pub use events::{tick_many_events, Event, EventDetector, EventKind, EventRun, Observation};
+pub use fixed::{Fixed, FixedPoint, SimError};
+pub use force::{
+    tick_force, tick_force_into, tick_many_force, Coulomb, Drag, ForceLaw, ForceModel, Gravity,
+    PowerLaw, Spring, Springs,
+};
+pub use frame::{centre_of_mass, centre_of_mass_frame, total_momentum};
+pub use kepler::{Anomaly, OrbitError, OrbitalElements, SystemBuilder};
+pub use math::{div_sqrt, isqrt, mul_div_sqrt_cubed};
+pub use scalar::{rescale, Scalar, WideFixed, WIDE_PRECISION};
+pub use symplectic::{Leapfrog, VelocityVerlet, Yoshida4};
+pub use trajectory::Trajectory;
+
+/// A body that can be integrated. Positions and velocities are handed around as
+/// arrays of three components, of which only the first `DIMENSIONS` are used, so
+/// planar bodies never do any work for a z axis.
+pub trait Body: Clone {
+    const DIMENSIONS: usize;
+
+    /// The numeric backend positions and velocities are held in.
+    type Scalar: Scalar;
+
+    /// Identifies the body within its system. A body feels the gravity of every other
+    /// body, so ids must be unique within a system but masses need not be.
+    fn get_id(&self) -> u32;
+
+    fn get_mass(&self) -> u64;
+
+    /// Bodies collide when their spheres touch, a radius of 0 is a point.
+    fn get_radius(&self) -> u64;
+
+    fn get_kind(&self) -> BodyKind;
+
+    fn get_position(&self) -> [Self::Scalar; 3];
+
+    fn get_velocity(&self) -> [Self::Scalar; 3];
+
+    /// A free body made up of the parts the getters above return.
+    fn from_parts(
+        id: u32,
+        mass: u64,
+        radius: u64,
+        position: [Self::Scalar; 3],
+        velocity: [Self::Scalar; 3],
+    ) -> Self;
+
+    /// The same body with a new position and velocity.
+    fn with_motion(&self, position: [Self::Scalar; 3], velocity: [Self::Scalar; 3]) -> Self;
+
+    /// The same body with a new mass and radius.
+    fn with_mass(&self, mass: u64, radius: u64) -> Self;
+
+    /// The same body of another kind.
+    fn with_kind(&self, kind: BodyKind) -> Self;
+
+    fn apply<F>(&self, func: F) -> Result<Self, SimError>
+    where
+        F: Fn(Self::Scalar) -> Result<Self::Scalar, SimError>,
+    {
+        let mut position = self.get_position();
+        let mut velocity = self.get_velocity();
+        for axis in 0..Self::DIMENSIONS {
+            position[axis] = func(position[axis])?;
+            velocity[axis] = func(velocity[axis])?;
+        }
+
+        Ok(self.with_motion(position, velocity))
+    }
+
+    fn apply_other<F>(&self, other_state: &Self, func: F) -> Result<Self, SimError>
+    where
+        F: Fn(Self::Scalar, Self::Scalar) -> Result<Self::Scalar, SimError>,
+    {
+        let mut position = self.get_position();
+        let mut velocity = self.get_velocity();
+        let other_position = other_state.get_position();
+        let other_velocity = other_state.get_velocity();
+        for axis in 0..Self::DIMENSIONS {
+            position[axis] = func(position[axis], other_position[axis])?;
+            velocity[axis] = func(velocity[axis], other_velocity[axis])?;
+        }
+
+        Ok(self.with_motion(position, velocity))
+    }
+}
+
+fn rescale_scalar<S: Scalar, T: Scalar>(value: S) -> Result<T, SimError> {
+    T::from_scaled(rescale(value.to_scaled(), S::SCALE, T::SCALE)?)
+}
+
+fn rescale_unsigned<S: Scalar, T: Scalar>(value: u64) -> Result<u64, SimError> {
+    u64::try_from(rescale(value as i128, S::SCALE, T::SCALE)?).map_err(|_| SimError::Overflow)
+}
+
+/// How a body takes part in the simulation. Tokens minted before bodies had kinds hold
+/// only free bodies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
+pub enum BodyKind {
+    /// Pulls the other bodies and is pulled by them.
+    #[default]
+    Free,
+    /// Pulls the other bodies but never moves, as if its inertia were infinite. It
+    /// keeps the position and velocity it starts with.
+    Anchor,
+    /// Is pulled by the other bodies but pulls none of them, whatever its mass. Each
+    /// tracer is evaluated against the other bodies alone, so N bodies and M tracers
+    /// cost O(N^2 + N M) rather than O((N + M)^2).
+    Tracer,
+}
+
+impl BodyKind {
+    pub fn from_id(id: u8) -> Option<BodyKind> {
+        match id {
+            0 => Some(BodyKind::Free),
+            1 => Some(BodyKind::Anchor),
+            2 => Some(BodyKind::Tracer),
+            _ => None,
+        }
+    }
+
+    pub fn id(&self) -> u8 {
+        match self {
+            BodyKind::Free => 0,
+            BodyKind::Anchor => 1,
+            BodyKind::Tracer => 2,
+        }
+    }
+
+    pub fn is_free(&self) -> bool {
+        *self == BodyKind::Free
+    }
+}
+
+impl TryFrom<u8> for BodyKind {
+    type Error = &'static str;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        BodyKind::from_id(id).ok_or("unknown body kind")
+    }
+}
+
+impl From<BodyKind> for u8 {
+    fn from(kind: BodyKind) -> u8 {
+        kind.id()
+    }
+}
+
+/// A planar body. Tokens are minted with a `FixedPoint` backend, by default `Fixed`,
+/// whose values are read and written as raw i64.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MotionState {
+pub struct MotionState<S = Fixed> {
+    id: u32,
     mass: u64,
-    x: i64,
-    y: i64,
-    vel_x: i64,
-    vel_y: i64,
+    #[serde(default)]
+    radius: u64,
+    /// Left out for free bodies, as bodies were written before they had kinds.
+    #[serde(default, skip_serializing_if = "BodyKind::is_free")]
+    kind: BodyKind,
+    x: S,
+    y: S,
+    vel_x: S,
+    vel_y: S,
+}
+
+impl<const SCALE: i64> MotionState<FixedPoint<SCALE>> {
+    pub fn get_x(&self) -> i64 {
+        self.x.raw()
+    }
+
+    pub fn get_y(&self) -> i64 {
+        self.y.raw()
+    }
+
+    pub fn get_vel_x(&self) -> i64 {
+        self.vel_x.raw()
+    }
+
+    pub fn get_vel_y(&self) -> i64 {
+        self.vel_y.raw()
+    }
+
+    /// A body at a scale of `SCALE`, see `new` for the default scale.
+    pub fn from_raw(id: u32, mass: u64, x: i64, y: i64, vel_x: i64, vel_y: i64) -> Self {
+        Self {
+            id,
+            mass,
+            radius: 0,
+            kind: BodyKind::Free,
+            x: FixedPoint::from_raw(x),
+            y: FixedPoint::from_raw(y),
+            vel_x: FixedPoint::from_raw(vel_x),
+            vel_y: FixedPoint::from_raw(vel_y),
+        }
+    }
 }
 
 impl MotionState {
+    pub fn new(id: u32, mass: u64, x: i64, y: i64, vel_x: i64, vel_y: i64) -> Self {
+        Self::from_raw(id, mass, x, y, vel_x, vel_y)
+    }
+}
+
+impl<S: Scalar> MotionState<S> {
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
     pub fn get_mass(&self) -> u64 {
         self.mass
     }
 
+    pub fn get_radius(&self) -> u64 {
+        self.radius
+    }
+
+    pub fn get_kind(&self) -> BodyKind {
+        self.kind
+    }
+
+    pub fn with_radius(self, radius: u64) -> Self {
+        Self { radius, ..self }
+    }
+
+    /// The same body in another backend or at another scale, masses and radii
+    /// included. Values go through integers at each `Scalar::SCALE`, so converting
+    /// `Fixed` to `WideFixed` or to a finer `FixedPoint` is exact.
+    pub fn convert<T: Scalar>(&self) -> Result<MotionState<T>, SimError> {
+        Ok(MotionState {
+            id: self.id,
+            mass: rescale_unsigned::<S, T>(self.mass)?,
+            radius: rescale_unsigned::<S, T>(self.radius)?,
+            kind: self.kind,
+            x: rescale_scalar(self.x)?,
+            y: rescale_scalar(self.y)?,
+            vel_x: rescale_scalar(self.vel_x)?,
+            vel_y: rescale_scalar(self.vel_y)?,
+        })
+    }
+}
+
+impl<S: Scalar> Body for MotionState<S> {
+    const DIMENSIONS: usize = 2;
+
+    type Scalar = S;
+
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn get_mass(&self) -> u64 {
+        self.mass
+    }
+
+    fn get_radius(&self) -> u64 {
+        self.radius
+    }
+
+    fn get_kind(&self) -> BodyKind {
+        self.kind
+    }
+
+    fn get_position(&self) -> [S; 3] {
+        [self.x, self.y, S::ZERO]
+    }
+
+    fn get_velocity(&self) -> [S; 3] {
+        [self.vel_x, self.vel_y, S::ZERO]
+    }
+
+    fn from_parts(id: u32, mass: u64, radius: u64, position: [S; 3], velocity: [S; 3]) -> Self {
+        MotionState {
+            id,
+            mass,
+            radius,
+            kind: BodyKind::Free,
+            x: position[0],
+            y: position[1],
+            vel_x: velocity[0],
+            vel_y: velocity[1],
+        }
+    }
+
+    fn with_motion(&self, position: [S; 3], velocity: [S; 3]) -> Self {
+        MotionState {
+            kind: self.kind,
+            ..MotionState::from_parts(self.id, self.mass, self.radius, position, velocity)
+        }
+    }
+
+    fn with_mass(&self, mass: u64, radius: u64) -> Self {
+        MotionState {
+            mass,
+            radius,
+            ..self.clone()
+        }
+    }
+
+    fn with_kind(&self, kind: BodyKind) -> Self {
+        MotionState {
+            kind,
+            ..self.clone()
+        }
+    }
+}
+
+/// A body in three dimensions, otherwise the same as `MotionState`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MotionState3D<S = Fixed> {
+    id: u32,
+    mass: u64,
+    #[serde(default)]
+    radius: u64,
+    #[serde(default, skip_serializing_if = "BodyKind::is_free")]
+    kind: BodyKind,
+    x: S,
+    y: S,
+    z: S,
+    vel_x: S,
+    vel_y: S,
+    vel_z: S,
+}
+
+impl<const SCALE: i64> MotionState3D<FixedPoint<SCALE>> {
     pub fn get_x(&self) -> i64 {
-        self.x
+        self.x.raw()
     }
 
     pub fn get_y(&self) -> i64 {
-        self.y
+        self.y.raw()
+    }
+
+    pub fn get_z(&self) -> i64 {
+        self.z.raw()
     }
 
     pub fn get_vel_x(&self) -> i64 {
-        self.vel_x
+        self.vel_x.raw()
     }
 
     pub fn get_vel_y(&self) -> i64 {
-        self.vel_y
+        self.vel_y.raw()
     }
 
-    pub fn new(mass: u64, x: i64, y: i64, vel_x: i64, vel_y: i64) -> Self {
+    pub fn get_vel_z(&self) -> i64 {
+        self.vel_z.raw()
+    }
+}
+
+impl MotionState3D {
+    /// A body at the raw `position` moving at the raw `velocity`, each as x, y and z.
+    pub fn new(id: u32, mass: u64, position: [i64; 3], velocity: [i64; 3]) -> Self {
+        let [x, y, z] = position;
+        let [vel_x, vel_y, vel_z] = velocity;
         Self {
+            id,
             mass,
-            x,
-            y,
-            vel_x,
-            vel_y,
+            radius: 0,
+            kind: BodyKind::Free,
+            x: Fixed::from_raw(x),
+            y: Fixed::from_raw(y),
+            z: Fixed::from_raw(z),
+            vel_x: Fixed::from_raw(vel_x),
+            vel_y: Fixed::from_raw(vel_y),
+            vel_z: Fixed::from_raw(vel_z),
         }
     }
+}
 
-    pub fn apply<F>(self: &MotionState, func: F) -> MotionState
-    where
-        F: Fn(i64) -> i64,
-    {
-        let new_x = func(self.x);
-        let new_y = func(self.y);
-        let new_vel_x = func(self.vel_x);
-        let new_vel_y = func(self.vel_y);
+impl<S: Scalar> MotionState3D<S> {
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
 
-        MotionState {
-            mass: self.mass,
-            x: new_x,
-            y: new_y,
-            vel_x: new_vel_x,
-            vel_y: new_vel_y,
-        }
+    pub fn get_mass(&self) -> u64 {
+        self.mass
     }
 
-    pub fn apply_other<F>(self: &MotionState, other_state: &MotionState, func: F) -> MotionState
-    where
-        F: Fn(i64, i64) -> i64,
-    {
-        let new_x = func(self.x, other_state.x);
-        let new_y = func(self.y, other_state.y);
-        let new_vel_x = func(self.vel_x, other_state.vel_x);
-        let new_vel_y = func(self.vel_y, other_state.vel_y);
+    pub fn get_radius(&self) -> u64 {
+        self.radius
+    }
 
-        MotionState {
-            mass: self.mass,
-            x: new_x,
-            y: new_y,
-            vel_x: new_vel_x,
-            vel_y: new_vel_y,
-        }
+    pub fn get_kind(&self) -> BodyKind {
+        self.kind
+    }
+
+    pub fn with_radius(self, radius: u64) -> Self {
+        Self { radius, ..self }
+    }
+
+    /// See `MotionState::convert`.
+    pub fn convert<T: Scalar>(&self) -> Result<MotionState3D<T>, SimError> {
+        Ok(MotionState3D {
+            id: self.id,
+            mass: rescale_unsigned::<S, T>(self.mass)?,
+            radius: rescale_unsigned::<S, T>(self.radius)?,
+            kind: self.kind,
+            x: rescale_scalar(self.x)?,
+            y: rescale_scalar(self.y)?,
+            z: rescale_scalar(self.z)?,
+            vel_x: rescale_scalar(self.vel_x)?,
+            vel_y: rescale_scalar(self.vel_y)?,
+            vel_z: rescale_scalar(self.vel_z)?,
+        })
     }
 }
 
-fn sqrt_heron(x: i64) -> i64 {
-    // https://en.wikipedia.org/wiki/Methods_of_computing_square_roots#Heron's_method
+impl<S: Scalar> Body for MotionState3D<S> {
+    const DIMENSIONS: usize = 3;
 
-    // CHRIS: TODO: dont accept negative in here, throw an error
-    if x < 0 {
-        return -1;
+    type Scalar = S;
+
+    fn get_id(&self) -> u32 {
+        self.id
     }
 
-    let mut x_old = x;
-    let mut x_new = (x_old + 1) / 2;
-    while x_new < x_old {
-        x_old = x_new;
-        x_new = (x_old + (x / x_old)) / 2;
+    fn get_mass(&self) -> u64 {
+        self.mass
     }
 
-    x_new
+    fn get_radius(&self) -> u64 {
+        self.radius
+    }
+
+    fn get_kind(&self) -> BodyKind {
+        self.kind
+    }
+
+    fn get_position(&self) -> [S; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    fn get_velocity(&self) -> [S; 3] {
+        [self.vel_x, self.vel_y, self.vel_z]
+    }
+
+    fn from_parts(id: u32, mass: u64, radius: u64, position: [S; 3], velocity: [S; 3]) -> Self {
+        MotionState3D {
+            id,
+            mass,
+            radius,
+            kind: BodyKind::Free,
+            x: position[0],
+            y: position[1],
+            z: position[2],
+            vel_x: velocity[0],
+            vel_y: velocity[1],
+            vel_z: velocity[2],
+        }
+    }
+
+    fn with_motion(&self, position: [S; 3], velocity: [S; 3]) -> Self {
+        MotionState3D {
+            kind: self.kind,
+            ..MotionState3D::from_parts(self.id, self.mass, self.radius, position, velocity)
+        }
+    }
+
+    fn with_mass(&self, mass: u64, radius: u64) -> Self {
+        MotionState3D {
+            mass,
+            radius,
+            ..self.clone()
+        }
+    }
+
+    fn with_kind(&self, kind: BodyKind) -> Self {
+        MotionState3D {
+            kind,
+            ..self.clone()
+        }
+    }
 }
 
 // CHRIS: TODO: better documentation and safety checks throughout
 
 pub const PRECISION: i64 = 100000000;
 
-pub fn gravitational_acceleration(grav_g: i64, mass: u64, d: i64, r: i64) -> i64 {
-    ((((-grav_g * mass as i64 * d / r) * PRECISION) / r) * PRECISION) / r
+/// `PRECISION` as a number of decimal places.
+pub const DECIMALS: u8 = 8;
+
+/// Acceleration along one axis towards a body of `mass` that is `d` away along that
+/// axis and `r` away in all, where `r` may be softened, see `grav_rate_func_softened`.
+/// Tokens are minted with its three divisions by `r`, which round more than
+/// `Scalar::mul_div_norm_cubed` does.
+pub fn gravitational_acceleration<S: Scalar>(
+    grav_g: S,
+    mass: u64,
+    d: S,
+    r: S,
+) -> Result<S, SimError> {
+    if r == S::ZERO {
+        return Err(SimError::ZeroDistance);
+    }
+
+    S::from_scaled(mass as i128)?
+        .checked_mul(grav_g)?
+        .checked_mul_int(-1)?
+        .mul_div(d, r)?
+        .checked_div(r)?
+        .checked_div(r)
+}
+
+/// The bodies of `system` that pull the others, that is all of them but the tracers.
+/// `None` when there are no tracers, `system` itself then being the sources.
+pub(crate) fn without_tracers<B: Body>(system: &[B]) -> Option<Vec<B>> {
+    system
+        .iter()
+        .any(|s| s.get_kind() == BodyKind::Tracer)
+        .then(|| {
+            system
+                .iter()
+                .filter(|s| s.get_kind() != BodyKind::Tracer)
+                .cloned()
+                .collect()
+        })
+}
+
+/// Rates of change of `state` under an acceleration of `acc`, anchors having none.
+pub(crate) fn rate_of<B: Body>(state: &B, acc: [B::Scalar; 3]) -> B {
+    match state.get_kind() {
+        BodyKind::Anchor => state.with_motion([B::Scalar::ZERO; 3], [B::Scalar::ZERO; 3]),
+        _ => state.with_motion(state.get_velocity(), acc),
+    }
+}
+
+pub fn grav_rate_func<B: Body>(state: &B, system: &[B]) -> Result<B, SimError> {
+    grav_rate_func_softened(state, system, 0)
+}
+
+/// `grav_rate_func` with Plummer softening, bodies a distance r apart attract as if
+/// they were sqrt(r^2 + softening^2) apart. This bounds the acceleration of close
+/// or coincident bodies, a softening of 0 being plain gravity.
+pub fn grav_rate_func_softened<B: Body>(
+    state: &B,
+    system: &[B],
+    softening: i64,
+) -> Result<B, SimError> {
+    let params = PhysicsParams {
+        softening,
+        ..PhysicsParams::at_scale(B::Scalar::SCALE)
+    };
+    grav_rate_func_params(state, system, &params)
+}
+
+/// Rates of change of `state` under the gravitational constant and softening of
+/// `params`. The time step and scheme of `params` are not used here.
+///
+/// This is the reference ordering for gravity: the pulls of the other bodies are
+/// summed in the order they come in `system`, each found by `gravitational_acceleration`
+/// from the difference of the positions of `state` and the other body.
+pub fn grav_rate_func_params<B: Body>(
+    state: &B,
+    system: &[B],
+    params: &PhysicsParams,
+) -> Result<B, SimError> {
+    Gravity.rate_func(state, system, params)
 }
 
-pub fn grav_rate_func(state: &MotionState, system: &Vec<MotionState>) -> MotionState {
-    let mut acc_x = 0;
-    let mut acc_y = 0;
+/// Adds to `acc` the gravity on `state`, see `grav_rate_func_params`.
+pub(crate) fn add_gravity<B: Body>(
+    acc: &mut [B::Scalar; 3],
+    state: &B,
+    system: &[B],
+    params: &PhysicsParams,
+) -> Result<(), SimError> {
+    let position = state.get_position();
+    let grav_g = B::Scalar::from_scaled(params.grav_g as i128)?;
+    let softening = B::Scalar::from_scaled(params.softening as i128)?;
 
     for s in system {
-        // use mass as an identifier
-        if s.mass != state.mass {
-            let dx = state.x - s.x;
-            let dy = state.y - s.y;
-            let r = sqrt_heron((dx * dx) + (dy * dy));
+        // skip only the body itself
+        if s.get_id() != state.get_id() {
+            add_attraction::<B>(
+                acc,
+                &position,
+                &s.get_position(),
+                s.get_mass(),
+                grav_g,
+                softening,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds to `acc` the acceleration of a body at `position` towards a `mass` at
+/// `other_position`.
+pub(crate) fn add_attraction<B: Body>(
+    acc: &mut [B::Scalar; 3],
+    position: &[B::Scalar; 3],
+    other_position: &[B::Scalar; 3],
+    mass: u64,
+    grav_g: B::Scalar,
+    softening: B::Scalar,
+) -> Result<(), SimError> {
+    let (d, r) = separation::<B>(position, other_position, softening)?;
+    add_pull::<B>(acc, &d, r, mass, grav_g)
+}
+
+/// The difference of `position` and `other_position` along each axis, and the
+/// distance between them softened by `softening`.
+fn separation<B: Body>(
+    position: &[B::Scalar; 3],
+    other_position: &[B::Scalar; 3],
+    softening: B::Scalar,
+) -> Result<([B::Scalar; 3], B::Scalar), SimError> {
+    // the softening is an extra component of the distance, ahead of the axes
+    let mut components = [softening; 4];
+    for axis in 0..B::DIMENSIONS {
+        components[axis + 1] = position[axis].checked_sub(other_position[axis])?;
+    }
+    let r = B::Scalar::norm(&components[..=B::DIMENSIONS])?;
+
+    let mut d = [B::Scalar::ZERO; 3];
+    d[..B::DIMENSIONS].copy_from_slice(&components[1..=B::DIMENSIONS]);
+    Ok((d, r))
+}
+
+/// Adds to `acc` the acceleration towards a `mass` that is `d` away along each axis
+/// and `r` away in all.
+fn add_pull<B: Body>(
+    acc: &mut [B::Scalar; 3],
+    d: &[B::Scalar; 3],
+    r: B::Scalar,
+    mass: u64,
+    grav_g: B::Scalar,
+) -> Result<(), SimError> {
+    for axis in 0..B::DIMENSIONS {
+        acc[axis] = acc[axis].checked_add(gravitational_acceleration(grav_g, mass, d[axis], r)?)?;
+    }
+    Ok(())
+}
+
+pub fn rk4<B, F>(time_period_sec: i64, state: &B, func: F) -> Result<B, SimError>
+where
+    B: Body,
+    F: Fn(&B) -> Result<B, SimError>,
+{
+    rk4_from_rate(time_period_sec, state, &func(state)?, func)
+}
+
+/// `rk4` given the rates of change at `state` itself, those of the first stage.
+fn rk4_from_rate<B, F>(time_period_sec: i64, state: &B, rate: &B, func: F) -> Result<B, SimError>
+where
+    B: Body,
+    F: Fn(&B) -> Result<B, SimError>,
+{
+    let time_period_sec = B::Scalar::from_scaled(time_period_sec as i128)?;
+    let scale = |k: B::Scalar| k.checked_mul(time_period_sec);
+    let half_step = |s: B::Scalar, k: B::Scalar| s.checked_add(k.checked_div_int(2)?);
+
+    let k1 = &rate.apply(scale)?;
+    let k2 = &func(&state.apply_other(k1, half_step)?)?.apply(scale)?;
+    let k3 = &func(&state.apply_other(k2, half_step)?)?.apply(scale)?;
+    // NOTE: the k4 stage is taken from k2 rather than k3. Kept as-is so that tokens
+    // minted with the per-body scheme replay identically, see `Scheme::CoupledRk4`.
+    let k4 = &func(&state.apply_other(k2, |s, k| s.checked_add(k))?)?.apply(scale)?;
+
+    let k1_k2 = &k1.apply_other(k2, |k1, k2| k1.checked_add(k2.checked_mul_int(2)?))?;
+    let k3_k4 = &k3.apply_other(k4, |k3, k4| k3.checked_mul_int(2)?.checked_add(k4))?;
+    let k1_k2_k3_k4 = &k1_k2.apply_other(k3_k4, |k1_k2, k3_k4| {
+        k1_k2.checked_add(k3_k4)?.checked_div_int(6)
+    })?;
+
+    state.apply_other(k1_k2_k3_k4, |s, k| s.checked_add(k))
+}
+
+/// Advances `system` with the original per-body scheme, see `Scheme::PerBodyRk4`.
+pub fn tick_per_body<B: Body>(time_period_sec: i64, system: &[B]) -> Result<Vec<B>, SimError> {
+    let params = PhysicsParams {
+        time_period_sec,
+        ..PhysicsParams::at_scale(B::Scalar::SCALE)
+    };
+    let mut next_system = system.to_vec();
+    tick_per_body_into(
+        time_period_sec,
+        system,
+        grav_rate_func_system_params(system, &params)?,
+        |s| grav_rate_func_params(s, system, &params),
+        &mut next_system,
+    )?;
+    Ok(next_system)
+}
+
+/// Steps every body of `system` by its own `rk4` into `next_system`, `func` giving the
+/// rates of change of a body against the unadvanced system. The first stage of every
+/// body is taken from `rates`, which are those of the unadvanced system itself.
+pub(crate) fn tick_per_body_into<B, F>(
+    time_period_sec: i64,
+    system: &[B],
+    rates: Vec<B>,
+    func: F,
+    next_system: &mut [B],
+) -> Result<(), SimError>
+where
+    B: Body,
+    F: Fn(&B) -> Result<B, SimError>,
+{
+    for ((state, rate), next_state) in system.iter().zip(&rates).zip(next_system) {
+        *next_state = rk4_from_rate(time_period_sec, state, rate, &func)?;
+    }
+    Ok(())
+}
+
+pub fn tick_many_per_body<B: Body>(
+    ticks: u32,
+    time_period_sec: i64,
+    system: &[B],
+) -> Result<Vec<B>, SimError> {
+    let mut next_system = system.to_vec();
+    for _ in 0..ticks {
+        next_system = tick_per_body(time_period_sec, &next_system)?;
+    }
+    Ok(next_system)
+}
+
+fn apply_system<B, F>(system: &[B], func: F) -> Result<Vec<B>, SimError>
+where
+    B: Body,
+    F: Fn(B::Scalar) -> Result<B::Scalar, SimError>,
+{
+    system.iter().map(|state| state.apply(&func)).collect()
+}
+
+/// Rates of change of every body in `system`, each evaluated against that same system.
+pub fn grav_rate_func_system<B: Body>(system: &[B]) -> Result<Vec<B>, SimError> {
+    grav_rate_func_system_params(system, &PhysicsParams::at_scale(B::Scalar::SCALE))
+}
+
+/// `grav_rate_func_params` for every body at once. Each pair of bodies is visited
+/// once and their distance, and so its square root, is found once for both. The pull
+/// on either body still comes from the difference of its own position and the
+/// other's, and each body still sums the pulls in the order of `system`, so the rates
+/// are bit-identical to those of `grav_rate_func_params`. Only the error reported when
+/// several bodies fail may differ.
+pub fn grav_rate_func_system_params<B: Body>(
+    system: &[B],
+    params: &PhysicsParams,
+) -> Result<Vec<B>, SimError> {
+    Gravity.rate_func_system(system, params)
+}
 
-            // assume gravity of 1
-            acc_x += gravitational_acceleration(1, s.mass, dx, r);
-            acc_y += gravitational_acceleration(1, s.mass, dy, r);
+/// Adds to `accs` the gravity on every body of `system`, pairwise, see
+/// `grav_rate_func_system_params`.
+pub(crate) fn add_gravity_pairwise<B: Body>(
+    accs: &mut [[B::Scalar; 3]],
+    system: &[B],
+    params: &PhysicsParams,
+) -> Result<(), SimError> {
+    let grav_g = B::Scalar::from_scaled(params.grav_g as i128)?;
+    let softening = B::Scalar::from_scaled(params.softening as i128)?;
+
+    // body j is pulled by the bodies before it as their rows come, and by the rest in
+    // its own row, so in the order of `system` either way
+    for (i, state) in system.iter().enumerate() {
+        let position = state.get_position();
+        let (head, tail) = accs.split_at_mut(i + 1);
+        for (other_state, other_acc) in system[i + 1..].iter().zip(tail) {
+            // skip only the body itself
+            if other_state.get_id() == state.get_id() {
+                continue;
+            }
+
+            let other_position = other_state.get_position();
+            let (d, r) = separation::<B>(&position, &other_position, softening)?;
+            add_pull::<B>(&mut head[i], &d, r, other_state.get_mass(), grav_g)?;
+
+            let mut other_d = [B::Scalar::ZERO; 3];
+            for axis in 0..B::DIMENSIONS {
+                other_d[axis] = other_position[axis].checked_sub(position[axis])?;
+            }
+            add_pull::<B>(other_acc, &other_d, r, state.get_mass(), grav_g)?;
         }
     }
+    Ok(())
+}
+
+/// Advances a whole system by one step, given a function returning the rates of
+/// change of every body in a system.
+pub trait Integrator {
+    fn step<B, F>(&self, time_period_sec: i64, system: &[B], func: F) -> Result<Vec<B>, SimError>
+    where
+        B: Body,
+        F: Fn(&[B]) -> Result<Vec<B>, SimError>;
+
+    /// `step` writing the advanced system into `next_system`, which holds as many bodies
+    /// as `system`, so that a caller stepping again and again can reuse its buffers.
+    fn step_into<B, F>(
+        &self,
+        time_period_sec: i64,
+        system: &[B],
+        func: F,
+        next_system: &mut [B],
+    ) -> Result<(), SimError>
+    where
+        B: Body,
+        F: Fn(&[B]) -> Result<Vec<B>, SimError>,
+    {
+        next_system.clone_from_slice(&self.step(time_period_sec, system, func)?);
+        Ok(())
+    }
+}
+
+/// Integer weights over a common denominator, so that combining stages costs a
+/// single division per component.
+#[derive(Debug, Clone, Copy)]
+pub struct Weights {
+    pub numerators: &'static [i64],
+    pub denominator: i64,
+}
+
+/// An explicit Runge-Kutta method. `a` holds one row per stage after the first and
+/// `b` the weights of the final combination. The rate functions here do not depend
+/// on time, so the `c` column is not needed.
+#[derive(Debug, Clone, Copy)]
+pub struct ButcherTableau {
+    pub a: &'static [Weights],
+    pub b: Weights,
+}
+
+pub const EULER: ButcherTableau = ButcherTableau {
+    a: &[],
+    b: Weights {
+        numerators: &[1],
+        denominator: 1,
+    },
+};
+
+pub const HEUN: ButcherTableau = ButcherTableau {
+    a: &[Weights {
+        numerators: &[1],
+        denominator: 1,
+    }],
+    b: Weights {
+        numerators: &[1, 1],
+        denominator: 2,
+    },
+};
+
+/// Kutta's third order method.
+pub const RK3: ButcherTableau = ButcherTableau {
+    a: &[
+        Weights {
+            numerators: &[1],
+            denominator: 2,
+        },
+        Weights {
+            numerators: &[-1, 2],
+            denominator: 1,
+        },
+    ],
+    b: Weights {
+        numerators: &[1, 4, 1],
+        denominator: 6,
+    },
+};
 
-    let d_vx = acc_x;
-    let d_vy = acc_y;
+/// Classic rk4.
+pub const RK4: ButcherTableau = ButcherTableau {
+    a: &[
+        Weights {
+            numerators: &[1],
+            denominator: 2,
+        },
+        Weights {
+            numerators: &[0, 1],
+            denominator: 2,
+        },
+        Weights {
+            numerators: &[0, 0, 1],
+            denominator: 1,
+        },
+    ],
+    b: Weights {
+        numerators: &[1, 2, 2, 1],
+        denominator: 6,
+    },
+};
 
-    let d_x = state.vel_x;
-    let d_y = state.vel_y;
+/// Kutta's 3/8-rule fourth order method.
+pub const RK38: ButcherTableau = ButcherTableau {
+    a: &[
+        Weights {
+            numerators: &[1],
+            denominator: 3,
+        },
+        Weights {
+            numerators: &[-1, 3],
+            denominator: 3,
+        },
+        Weights {
+            numerators: &[1, -1, 1],
+            denominator: 1,
+        },
+    ],
+    b: Weights {
+        numerators: &[1, 3, 3, 1],
+        denominator: 8,
+    },
+};
 
-    MotionState {
-        mass: state.mass,
-        x: d_x,
-        y: d_y,
-        vel_x: d_vx,
-        vel_y: d_vy,
+/// Returns `system` plus the weighted sum of the stages `ks`, see `Scalar::weighted_sum`.
+fn add_stages<B: Body>(system: &[B], ks: &[Vec<B>], weights: &Weights) -> Result<Vec<B>, SimError> {
+    let mut next_system = system.to_vec();
+    add_stages_into(system, ks, weights, &mut next_system)?;
+    Ok(next_system)
+}
+
+/// `add_stages` writing the sum into `next_system`.
+fn add_stages_into<B: Body>(
+    system: &[B],
+    ks: &[Vec<B>],
+    weights: &Weights,
+    next_system: &mut [B],
+) -> Result<(), SimError> {
+    let denominator = weights.denominator as i128;
+    for (i, (state, next_state)) in system.iter().zip(next_system).enumerate() {
+        let terms = || {
+            ks.iter()
+                .zip(weights.numerators)
+                .map(|(k, n)| (*n as i128, &k[i]))
+        };
+        let mut position = state.get_position();
+        let mut velocity = state.get_velocity();
+        for axis in 0..B::DIMENSIONS {
+            position[axis] = position[axis].checked_add(B::Scalar::weighted_sum(
+                terms().map(|(n, k)| (n, k.get_position()[axis])),
+                denominator,
+            )?)?;
+            velocity[axis] = velocity[axis].checked_add(B::Scalar::weighted_sum(
+                terms().map(|(n, k)| (n, k.get_velocity()[axis])),
+                denominator,
+            )?)?;
+        }
+        *next_state = state.with_motion(position, velocity);
     }
+    Ok(())
 }
 
-pub fn rk4<F>(time_period_sec: i64, state: &MotionState, func: F) -> MotionState
+/// Evaluates the k-stages described by the rows `a`. Each k-stage is a full Vec of
+/// derivatives evaluated on the intermediate system, so every body is advanced
+/// through the stages together.
+fn compute_stages<B, F>(
+    a: &[Weights],
+    time_period_sec: i64,
+    system: &[B],
+    func: F,
+) -> Result<Vec<Vec<B>>, SimError>
 where
-    F: Fn(&MotionState) -> MotionState,
+    B: Body,
+    F: Fn(&[B]) -> Result<Vec<B>, SimError>,
 {
-    let k1 = &func(&state).apply(|k| (k * time_period_sec) / PRECISION);
-    let k2 = &func(&state.apply_other(&k1, |s, k| s + k / 2))
-        .apply(|k| (k * time_period_sec) / PRECISION);
-    let k3 = &func(&state.apply_other(&k2, |s, k| s + k / 2))
-        .apply(|k| (k * time_period_sec) / PRECISION);
-    let k4 =
-        &func(&state.apply_other(&k2, |s, k| s + k)).apply(|k| (k * time_period_sec) / PRECISION);
+    let time_period_sec = B::Scalar::from_scaled(time_period_sec as i128)?;
+    let scale = |k: B::Scalar| k.checked_mul(time_period_sec);
 
-    let k1_k2 = &k1.apply_other(&k2, |k1, k2| k1 + 2 * k2);
-    let k3_k4 = &k3.apply_other(&k4, |k3, k4| 2 * k3 + k4);
-    let k1_k2_k3_k4 = &k1_k2.apply_other(&k3_k4, |k1_k2, k3_k4| (k1_k2 + k3_k4) / 6);
+    let mut ks = Vec::with_capacity(a.len() + 1);
+    ks.push(apply_system(&func(system)?, scale)?);
+    for weights in a {
+        let stage = add_stages(system, &ks, weights)?;
+        ks.push(apply_system(&func(&stage)?, scale)?);
+    }
+    Ok(ks)
+}
+
+impl Integrator for ButcherTableau {
+    fn step<B, F>(&self, time_period_sec: i64, system: &[B], func: F) -> Result<Vec<B>, SimError>
+    where
+        B: Body,
+        F: Fn(&[B]) -> Result<Vec<B>, SimError>,
+    {
+        let ks = compute_stages(self.a, time_period_sec, system, func)?;
+        add_stages(system, &ks, &self.b)
+    }
 
-    return state.apply_other(k1_k2_k3_k4, |s, k| s + k);
+    fn step_into<B, F>(
+        &self,
+        time_period_sec: i64,
+        system: &[B],
+        func: F,
+        next_system: &mut [B],
+    ) -> Result<(), SimError>
+    where
+        B: Body,
+        F: Fn(&[B]) -> Result<Vec<B>, SimError>,
+    {
+        let ks = compute_stages(self.a, time_period_sec, system, func)?;
+        add_stages_into(system, &ks, &self.b, next_system)
+    }
+}
+
+/// Integration scheme used to advance a system. Each token records the scheme it was
+/// minted with so that it can always be replayed exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
+pub enum Scheme {
+    /// The original scheme: each body is stepped by its own `rk4` against the
+    /// unadvanced system. All tokens minted before schemes existed use this.
+    PerBodyRk4,
+    /// Classic rk4 with every stage advancing all bodies together.
+    CoupledRk4,
+    Euler,
+    Heun,
+    Rk3,
+    Rk38,
+    Leapfrog,
+    VelocityVerlet,
+    Yoshida4,
+}
+
+impl Scheme {
+    pub fn from_id(id: u8) -> Option<Scheme> {
+        match id {
+            0 => Some(Scheme::PerBodyRk4),
+            1 => Some(Scheme::CoupledRk4),
+            2 => Some(Scheme::Euler),
+            3 => Some(Scheme::Heun),
+            4 => Some(Scheme::Rk3),
+            5 => Some(Scheme::Rk38),
+            6 => Some(Scheme::Leapfrog),
+            7 => Some(Scheme::VelocityVerlet),
+            8 => Some(Scheme::Yoshida4),
+            _ => None,
+        }
+    }
+
+    pub fn id(&self) -> u8 {
+        match self {
+            Scheme::PerBodyRk4 => 0,
+            Scheme::CoupledRk4 => 1,
+            Scheme::Euler => 2,
+            Scheme::Heun => 3,
+            Scheme::Rk3 => 4,
+            Scheme::Rk38 => 5,
+            Scheme::Leapfrog => 6,
+            Scheme::VelocityVerlet => 7,
+            Scheme::Yoshida4 => 8,
+        }
+    }
+
+    /// The tableau behind the scheme, `None` for the per-body and symplectic schemes.
+    pub fn tableau(&self) -> Option<&'static ButcherTableau> {
+        match self {
+            Scheme::CoupledRk4 => Some(&RK4),
+            Scheme::Euler => Some(&EULER),
+            Scheme::Heun => Some(&HEUN),
+            Scheme::Rk3 => Some(&RK3),
+            Scheme::Rk38 => Some(&RK38),
+            _ => None,
+        }
+    }
 }
 
-pub fn tick(time_period_sec: i64, system: &Vec<MotionState>) -> Vec<MotionState> {
-    let mut next_system = Vec::new();
+impl TryFrom<u8> for Scheme {
+    type Error = &'static str;
 
-    for state in system {
-        next_system.push(rk4(time_period_sec, &state, |s| grav_rate_func(s, &system)));
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        Scheme::from_id(id).ok_or("unknown scheme")
     }
+}
 
-    next_system
+impl From<Scheme> for u8 {
+    fn from(scheme: Scheme) -> u8 {
+        scheme.id()
+    }
+}
+
+/// Everything besides the bodies that determines how a system evolves. The
+/// gravitational constant, time step and softening are scaled like the bodies, so
+/// a `grav_g` of one unit, `Scalar::SCALE`, is the gravity tokens have always used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhysicsParams {
+    pub grav_g: i64,
+    pub time_period_sec: i64,
+    /// Plummer softening length, see `grav_rate_func_softened`.
+    pub softening: i64,
+    pub scheme: Scheme,
+}
+
+impl PhysicsParams {
+    /// The parameters tokens were minted with before they could be chosen, for
+    /// bodies scaled by `scale`: a gravity of 1, a step of 0.001 and no softening.
+    pub fn at_scale(scale: i64) -> PhysicsParams {
+        PhysicsParams {
+            grav_g: scale,
+            time_period_sec: scale / 1000,
+            softening: 0,
+            scheme: Scheme::PerBodyRk4,
+        }
+    }
+}
+
+impl Default for PhysicsParams {
+    fn default() -> Self {
+        PhysicsParams::at_scale(PRECISION)
+    }
+}
+
+/// Advances `system` by one step under `params`.
+pub fn tick<B: Body>(params: &PhysicsParams, system: &[B]) -> Result<Vec<B>, SimError> {
+    tick_force(params, &Gravity, system)
+}
+
+/// Advances `system` by one step of the scheme of `params` into `next_system`, which
+/// holds as many bodies. `per_body` takes the step for `Scheme::PerBodyRk4`, the other
+/// schemes stepping the whole system with `func` giving the rates of change of a whole
+/// system.
+pub(crate) fn tick_with_into<B, P, F>(
+    params: &PhysicsParams,
+    system: &[B],
+    per_body: P,
+    func: F,
+    next_system: &mut [B],
+) -> Result<(), SimError>
+where
+    B: Body,
+    P: FnOnce(&mut [B]) -> Result<(), SimError>,
+    F: Fn(&[B]) -> Result<Vec<B>, SimError>,
+{
+    let time_period_sec = params.time_period_sec;
+    if let Some(tableau) = params.scheme.tableau() {
+        return tableau.step_into(time_period_sec, system, func, next_system);
+    }
+    match params.scheme {
+        Scheme::Leapfrog => Leapfrog.step_into(time_period_sec, system, func, next_system),
+        Scheme::VelocityVerlet => {
+            VelocityVerlet.step_into(time_period_sec, system, func, next_system)
+        }
+        Scheme::Yoshida4 => Yoshida4.step_into(time_period_sec, system, func, next_system),
+        _ => per_body(next_system),
+    }
 }
 
-pub fn tick_many(ticks: u32, time_period_sec: i64, system: &Vec<MotionState>) -> Vec<MotionState> {
-    let mut next_system = system.clone();
+pub fn tick_many<B: Body>(
+    params: &PhysicsParams,
+    ticks: u32,
+    system: &[B],
+) -> Result<Vec<B>, SimError> {
+    let mut next_system = system.to_vec();
     for _ in 0..ticks {
-        next_system = tick(time_period_sec, &next_system);
+        next_system = tick(params, &next_system)?;
     }
-    next_system
+    Ok(next_system)
 }
@@ -0,0 +1,214 @@
+use super::{
+    add_stages, compute_stages, Body, ForceModel, PhysicsParams, Scalar, SimError, Weights,
+};
+
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// A pair of explicit Runge-Kutta methods sharing their stages. `b` gives the higher
+/// order solution, which is the one carried forward, and `b_hat` the lower order
+/// solution used to estimate the error of each step.
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedTableau {
+    pub a: &'static [Weights],
+    pub b: Weights,
+    pub b_hat: Weights,
+}
+
+/// Runge-Kutta-Fehlberg 4(5).
+pub const RKF45: EmbeddedTableau = EmbeddedTableau {
+    a: &[
+        Weights {
+            numerators: &[1],
+            denominator: 4,
+        },
+        Weights {
+            numerators: &[3, 9],
+            denominator: 32,
+        },
+        Weights {
+            numerators: &[1932, -7200, 7296],
+            denominator: 2197,
+        },
+        Weights {
+            numerators: &[8341, -32832, 29440, -845],
+            denominator: 4104,
+        },
+        Weights {
+            numerators: &[-6080, 41040, -28352, 9295, -5643],
+            denominator: 20520,
+        },
+    ],
+    b: Weights {
+        numerators: &[33440, 0, 146432, 142805, -50787, 10260],
+        denominator: 282150,
+    },
+    b_hat: Weights {
+        numerators: &[2375, 0, 11264, 10985, -4104, 0],
+        denominator: 20520,
+    },
+};
+
+/// Dormand-Prince 5(4).
+pub const DORMAND_PRINCE: EmbeddedTableau = EmbeddedTableau {
+    a: &[
+        Weights {
+            numerators: &[1],
+            denominator: 5,
+        },
+        Weights {
+            numerators: &[3, 9],
+            denominator: 40,
+        },
+        Weights {
+            numerators: &[44, -168, 160],
+            denominator: 45,
+        },
+        Weights {
+            numerators: &[19372, -76080, 64448, -1908],
+            denominator: 6561,
+        },
+        Weights {
+            numerators: &[477901, -1806240, 1495424, 46746, -45927],
+            denominator: 167904,
+        },
+        Weights {
+            numerators: &[12985, 0, 64000, 92750, -45927, 18656],
+            denominator: 142464,
+        },
+    ],
+    b: Weights {
+        numerators: &[12985, 0, 64000, 92750, -45927, 18656, 0],
+        denominator: 142464,
+    },
+    b_hat: Weights {
+        numerators: &[1921409, 0, 9690880, 13122270, -5802111, 1902912, 534240],
+        denominator: 21369600,
+    },
+};
+
+/// The embedded pair with the id `id`, 0 being `RKF45` and 1 `DORMAND_PRINCE`.
+pub fn embedded_tableau(id: u8) -> Option<&'static EmbeddedTableau> {
+    match id {
+        0 => Some(&RKF45),
+        1 => Some(&DORMAND_PRINCE),
+        _ => None,
+    }
+}
+
+/// Step size control for `integrate_adaptive`. All values are scaled like the bodies,
+/// see `Scalar::SCALE`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StepControl {
+    /// Largest accepted difference between the two solutions of the pair, in any
+    /// position or velocity component of any body.
+    pub tolerance: i64,
+    pub initial_time_period_sec: i64,
+    /// Steps of this size are accepted whatever their error, so a run takes at most
+    /// `target_time_sec / min_time_period_sec` steps.
+    pub min_time_period_sec: i64,
+    pub max_time_period_sec: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdaptiveRun<B> {
+    pub system: Vec<B>,
+    /// The accepted step sizes in order, they sum to the target time.
+    pub time_periods_sec: Vec<i64>,
+}
+
+/// `|x|` scaled by `Scalar::SCALE`, saturating at `i64::MAX`.
+fn scaled_abs<S: Scalar>(x: S) -> i64 {
+    i64::try_from(x.to_scaled().unsigned_abs()).unwrap_or(i64::MAX)
+}
+
+fn max_difference<B: Body>(system: &[B], other_system: &[B]) -> i64 {
+    let mut difference = 0;
+    for (s, o) in system.iter().zip(other_system) {
+        let (position, other_position) = (s.get_position(), o.get_position());
+        let (velocity, other_velocity) = (s.get_velocity(), o.get_velocity());
+        for axis in 0..B::DIMENSIONS {
+            difference = difference
+                .max(scaled_abs(
+                    position[axis].saturating_sub(other_position[axis]),
+                ))
+                .max(scaled_abs(
+                    velocity[axis].saturating_sub(other_velocity[axis]),
+                ));
+        }
+    }
+    difference
+}
+
+/// Integrates `system` forward by `target_time_sec` of simulated time, sizing each
+/// step from the error estimate of the embedded pair.
+///
+/// Step sizes are only ever halved or doubled, so a run is fully determined by its
+/// inputs and uses no floating point. The local error of both pairs is fifth order
+/// in the step size: a rejected step is halved, cutting its error by about 32, and
+/// a step whose error is within 1/32 of the tolerance is doubled for the next one.
+/// The last step is shortened to land exactly on the target time.
+pub fn integrate_adaptive<B, F>(
+    tableau: &EmbeddedTableau,
+    target_time_sec: i64,
+    control: &StepControl,
+    system: &[B],
+    func: F,
+) -> Result<AdaptiveRun<B>, SimError>
+where
+    B: Body,
+    F: Fn(&[B]) -> Result<Vec<B>, SimError>,
+{
+    let min_time_period_sec = control.min_time_period_sec.max(1);
+    let max_time_period_sec = control.max_time_period_sec.max(min_time_period_sec);
+
+    let mut next_system = system.to_vec();
+    let mut time_periods_sec = Vec::new();
+    let mut elapsed_sec = 0;
+    let mut time_period_sec = control
+        .initial_time_period_sec
+        .max(min_time_period_sec)
+        .min(max_time_period_sec);
+
+    while elapsed_sec < target_time_sec {
+        let step_sec = time_period_sec.min(target_time_sec - elapsed_sec);
+
+        let ks = compute_stages(tableau.a, step_sec, &next_system, &func)?;
+        let high = add_stages(&next_system, &ks, &tableau.b)?;
+        let low = add_stages(&next_system, &ks, &tableau.b_hat)?;
+        let error = max_difference(&high, &low);
+
+        if error > control.tolerance && step_sec > min_time_period_sec {
+            time_period_sec = (step_sec / 2).max(min_time_period_sec);
+            continue;
+        }
+
+        next_system = high;
+        elapsed_sec += step_sec;
+        time_periods_sec.push(step_sec);
+
+        if error <= control.tolerance / 32 {
+            time_period_sec = time_period_sec.saturating_mul(2).min(max_time_period_sec);
+        }
+    }
+
+    Ok(AdaptiveRun {
+        system: next_system,
+        time_periods_sec,
+    })
+}
+
+/// `integrate_adaptive` under `force`, with the gravitational constant and softening of
+/// `params`. Its step and scheme are left to `control` and `tableau`.
+pub fn tick_adaptive<B: Body, F: ForceModel>(
+    params: &PhysicsParams,
+    force: &F,
+    tableau: &EmbeddedTableau,
+    target_time_sec: i64,
+    control: &StepControl,
+    system: &[B],
+) -> Result<AdaptiveRun<B>, SimError> {
+    integrate_adaptive(tableau, target_time_sec, control, system, |system| {
+        force.rate_func_system(system, params)
+    })
+}
@@ -0,0 +1,297 @@
+use super::{
+    add_attraction, rate_of, tick_per_body_into, tick_with_into, without_tracers, Body, BodyKind,
+    PhysicsParams, Scalar, SimError,
+};
+
+use core::cmp::Ordering;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// Barnes-Hut approximates the pull of a distant group of bodies by that of a single
+// body holding their total mass at their centre of mass. Space is split into a tree
+// of cube cells, each halved along every axis into 4 children for planar systems and
+// 8 in 3D, until a cell holds a single body. The gravity on a body is then found by
+// walking the tree from the root, taking a cell as a whole when its size is small
+// next to its distance, by a ratio of the opening angle theta, and opening it
+// otherwise. An evaluation costs O(N log N) rather than the O(N^2) of the direct
+// sum. A theta of 0 opens every cell and leaves the direct sum, larger angles are
+// faster and less accurate.
+
+/// Gravity from a Barnes-Hut tree with an opening angle of `theta`, scaled like the
+/// bodies. An angle of 0.5 is the usual choice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BarnesHut {
+    pub theta: i64,
+}
+
+struct Cell<S> {
+    /// The bodies of the cell are `order[start..end]`.
+    start: usize,
+    end: usize,
+    /// Side of the cell, scaled like the bodies.
+    size: i128,
+    mass: u64,
+    centre_of_mass: [S; 3],
+    children: Vec<usize>,
+}
+
+/// The Barnes-Hut tree of a system, built once and walked for every body.
+pub struct Tree<B: Body> {
+    system: Vec<B>,
+    theta: B::Scalar,
+    /// Indices into `system`, ordered so that the bodies of every cell are adjacent.
+    order: Vec<usize>,
+    /// Where each body id is found in `order`.
+    ranks: BTreeMap<u32, usize>,
+    /// The root cell comes first.
+    cells: Vec<Cell<B::Scalar>>,
+}
+
+impl BarnesHut {
+    /// The tree of the bodies of `system` that pull the others, tracers being left out.
+    pub fn build<B: Body>(&self, system: &[B]) -> Result<Tree<B>, SimError> {
+        let sources = without_tracers(system);
+        let system = sources.as_deref().unwrap_or(system);
+        let positions: Vec<[i128; 3]> = system
+            .iter()
+            .map(|state| state.get_position().map(|x| x.to_scaled()))
+            .collect();
+
+        // the root cell bounds every body
+        let mut origin = [0; 3];
+        let mut size = 1;
+        if let Some(first) = positions.first() {
+            origin = *first;
+            let mut far = *first;
+            for position in &positions {
+                for axis in 0..B::DIMENSIONS {
+                    origin[axis] = origin[axis].min(position[axis]);
+                    far[axis] = far[axis].max(position[axis]);
+                }
+            }
+            for axis in 0..B::DIMENSIONS {
+                size = size.max(far[axis] - origin[axis] + 1);
+            }
+        }
+
+        let mut tree = Tree {
+            system: system.to_vec(),
+            theta: B::Scalar::from_scaled(self.theta as i128)?,
+            order: (0..system.len()).collect(),
+            ranks: BTreeMap::new(),
+            cells: Vec::new(),
+        };
+        tree.split(&positions, 0, system.len(), origin, size)?;
+        for (rank, &i) in tree.order.iter().enumerate() {
+            tree.ranks.insert(system[i].get_id(), rank);
+        }
+        Ok(tree)
+    }
+
+    /// Rates of change of every body in `system`, see `grav_rate_func_system_params`.
+    pub fn rate_func_system<B: Body>(
+        &self,
+        system: &[B],
+        params: &PhysicsParams,
+    ) -> Result<Vec<B>, SimError> {
+        let tree = self.build(system)?;
+        system
+            .iter()
+            .map(|state| tree.rate_func(state, params))
+            .collect()
+    }
+}
+
+impl<B: Body> Tree<B> {
+    /// Adds the cell of the bodies `order[start..end]`, whose positions lie within the
+    /// cube of side `size` at `origin`, and below it the cells it splits into.
+    fn split(
+        &mut self,
+        positions: &[[i128; 3]],
+        start: usize,
+        end: usize,
+        origin: [i128; 3],
+        size: i128,
+    ) -> Result<usize, SimError> {
+        let bodies = &self.order[start..end];
+        let mass = bodies
+            .iter()
+            .try_fold(0u64, |mass, &i| mass.checked_add(self.system[i].get_mass()))
+            .ok_or(SimError::Overflow)?;
+
+        // a massless cell pulls nothing, wherever its centre is taken to be
+        let mut centre_of_mass = [B::Scalar::ZERO; 3];
+        if mass > 0 {
+            for (axis, centre) in centre_of_mass.iter_mut().enumerate().take(B::DIMENSIONS) {
+                *centre = B::Scalar::weighted_sum(
+                    bodies.iter().map(|&i| {
+                        let state = &self.system[i];
+                        (state.get_mass() as i128, state.get_position()[axis])
+                    }),
+                    mass as i128,
+                )?;
+            }
+        }
+
+        let index = self.cells.len();
+        self.cells.push(Cell {
+            start,
+            end,
+            size,
+            mass,
+            centre_of_mass,
+            children: Vec::new(),
+        });
+        // bodies at the same position share a cell that can split no further
+        if end - start < 2 || size < 2 {
+            return Ok(index);
+        }
+
+        // sort the bodies by the child they fall in, one bit per axis
+        let half = (size + 1) / 2;
+        let child = |i: usize| -> usize {
+            (0..B::DIMENSIONS)
+                .filter(|&axis| positions[i][axis] >= origin[axis] + half)
+                .map(|axis| 1 << axis)
+                .sum()
+        };
+        self.order[start..end].sort_by_key(|&i| child(i));
+
+        let mut children = Vec::new();
+        let mut child_start = start;
+        while child_start < end {
+            let bits = child(self.order[child_start]);
+            let child_end = child_start
+                + self.order[child_start..end]
+                    .iter()
+                    .take_while(|&&i| child(i) == bits)
+                    .count();
+            let mut child_origin = origin;
+            for (axis, corner) in child_origin.iter_mut().enumerate().take(B::DIMENSIONS) {
+                if bits & (1 << axis) != 0 {
+                    *corner += half;
+                }
+            }
+            children.push(self.split(positions, child_start, child_end, child_origin, half)?);
+            child_start = child_end;
+        }
+        self.cells[index].children = children;
+        Ok(index)
+    }
+
+    /// Rates of change of `state` against the system of the tree, leaving out the body
+    /// with the same id, see `grav_rate_func_params`. `state` may be a tracer, which the
+    /// tree does not hold.
+    pub fn rate_func(&self, state: &B, params: &PhysicsParams) -> Result<B, SimError> {
+        let position = state.get_position();
+        let grav_g = B::Scalar::from_scaled(params.grav_g as i128)?;
+        let softening = B::Scalar::from_scaled(params.softening as i128)?;
+        let rank = self.ranks.get(&state.get_id()).copied();
+        let mut acc = [B::Scalar::ZERO; 3];
+        if state.get_kind() == BodyKind::Anchor {
+            return Ok(rate_of(state, acc));
+        }
+
+        let mut stack = Vec::from([0]);
+        while let Some(index) = stack.pop() {
+            let cell = &self.cells[index];
+            // a cell holding the body itself is always opened
+            let holds_state = rank.is_some_and(|rank| (cell.start..cell.end).contains(&rank));
+            if cell.children.is_empty() {
+                for &i in &self.order[cell.start..cell.end] {
+                    let s = &self.system[i];
+                    if s.get_id() != state.get_id() {
+                        add_attraction::<B>(
+                            &mut acc,
+                            &position,
+                            &s.get_position(),
+                            s.get_mass(),
+                            grav_g,
+                            softening,
+                        )?;
+                    }
+                }
+            } else if !holds_state && self.is_distant(&position, cell) {
+                add_attraction::<B>(
+                    &mut acc,
+                    &position,
+                    &cell.centre_of_mass,
+                    cell.mass,
+                    grav_g,
+                    softening,
+                )?;
+            } else {
+                stack.extend(&cell.children);
+            }
+        }
+
+        Ok(rate_of(state, acc))
+    }
+
+    /// Whether `cell` is far enough from `position` to be taken as a whole, that is
+    /// size / distance < theta.
+    fn is_distant(&self, position: &[B::Scalar; 3], cell: &Cell<B::Scalar>) -> bool {
+        if self.theta <= B::Scalar::ZERO {
+            return false;
+        }
+
+        let mut d = [B::Scalar::ZERO; 3];
+        for axis in 0..B::DIMENSIONS {
+            match position[axis].checked_sub(cell.centre_of_mass[axis]) {
+                Ok(component) => d[axis] = component,
+                Err(_) => return false,
+            }
+        }
+        match B::Scalar::from_scaled(cell.size).and_then(|size| size.checked_div(self.theta)) {
+            Ok(length) => B::Scalar::cmp_norm(&d[..B::DIMENSIONS], length) == Ordering::Greater,
+            Err(_) => false,
+        }
+    }
+}
+
+/// `tick` with gravity from a Barnes-Hut tree. The per-body scheme walks one tree of
+/// the unadvanced system for every body, as it evaluates every body against the
+/// unadvanced system.
+pub fn tick_barnes_hut<B: Body>(
+    params: &PhysicsParams,
+    barnes_hut: &BarnesHut,
+    system: &[B],
+) -> Result<Vec<B>, SimError> {
+    let mut next_system = system.to_vec();
+    tick_with_into(
+        params,
+        system,
+        |next_system| {
+            let tree = barnes_hut.build(system)?;
+            let rates = system
+                .iter()
+                .map(|state| tree.rate_func(state, params))
+                .collect::<Result<_, _>>()?;
+            tick_per_body_into(
+                params.time_period_sec,
+                system,
+                rates,
+                |s| tree.rate_func(s, params),
+                next_system,
+            )
+        },
+        |s| barnes_hut.rate_func_system(s, params),
+        &mut next_system,
+    )?;
+    Ok(next_system)
+}
+
+pub fn tick_many_barnes_hut<B: Body>(
+    params: &PhysicsParams,
+    barnes_hut: &BarnesHut,
+    ticks: u32,
+    system: &[B],
+) -> Result<Vec<B>, SimError> {
+    let mut next_system = system.to_vec();
+    for _ in 0..ticks {
+        next_system = tick_barnes_hut(params, barnes_hut, &next_system)?;
+    }
+    Ok(next_system)
+}
@@ -0,0 +1,243 @@
+use super::{tick_many_force, Body, BodyKind, ForceLaw, PhysicsParams, Scalar, Scheme, SimError};
+
+use core::fmt;
+
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// A checkpoint holds everything needed to carry on a run: the physics, the force law,
+// the tick reached and the bodies. The schemes keep nothing from one tick to the next
+// besides the scheme itself, so that is all the integrator state there is, and a
+// resumed run is bit-identical to one that never stopped. Checkpoints are encoded in a
+// fixed layout with every integer little-endian:
+//
+//   magic            4 bytes, "RKFC"
+//   version          u8, `CHECKPOINT_VERSION`
+//   dimensions       u8, 2 or 3
+//   scale            i64, the `Scalar::SCALE` of the bodies
+//   grav_g           i64
+//   time_period_sec  i64
+//   softening        i64
+//   scheme           u8, the id of the `Scheme`
+//   force            u32, the length of the `ForceLaw::encode` bytes that follow
+//   tick             u32
+//   bodies           u32, the number of bodies, each then following as
+//     id             u32
+//     mass           u64
+//     radius         u64
+//     kind           u8, the id of the `BodyKind`
+//     position       i64 per dimension
+//     velocity       i64 per dimension
+//
+// Positions and velocities are raw at `scale`, see `Scalar::to_scaled`. That is exact
+// for `FixedPoint`, the other backends being rounded to their scale.
+
+const MAGIC: [u8; 4] = *b"RKFC";
+
+/// The version of the checkpoint layout written by `Checkpoint::encode`.
+pub const CHECKPOINT_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointError {
+    /// The bytes end before the checkpoint does.
+    Truncated,
+    /// The bytes carry on after the checkpoint ends.
+    TrailingBytes,
+    /// The bytes do not start with the checkpoint magic.
+    NotACheckpoint,
+    UnsupportedVersion(u8),
+    /// The checkpoint holds bodies of another number of dimensions.
+    WrongDimensions(u8),
+    /// The checkpoint holds bodies at another scale.
+    WrongScale(i64),
+    UnknownScheme(u8),
+    UnknownKind(u8),
+    /// The checkpoint holds no `ForceLaw`, see `ForceLaw::decode`.
+    UnknownForce,
+    /// A value does not fit the backend of the bodies.
+    Overflow,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Truncated => write!(f, "checkpoint is truncated"),
+            CheckpointError::TrailingBytes => write!(f, "trailing bytes after checkpoint"),
+            CheckpointError::NotACheckpoint => write!(f, "not a checkpoint"),
+            CheckpointError::UnsupportedVersion(version) => {
+                write!(f, "unsupported checkpoint version {}", version)
+            }
+            CheckpointError::WrongDimensions(dimensions) => {
+                write!(f, "checkpoint has {} dimensions", dimensions)
+            }
+            CheckpointError::WrongScale(scale) => write!(f, "checkpoint has a scale of {}", scale),
+            CheckpointError::UnknownScheme(scheme) => write!(f, "unknown scheme {}", scheme),
+            CheckpointError::UnknownKind(kind) => write!(f, "unknown body kind {}", kind),
+            CheckpointError::UnknownForce => write!(f, "unknown force law"),
+            CheckpointError::Overflow => write!(f, "checkpoint value out of range"),
+        }
+    }
+}
+
+/// A run stopped at `tick`, to be resumed where it left off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint<B> {
+    pub params: PhysicsParams,
+    pub force: ForceLaw,
+    pub tick: u32,
+    pub system: Vec<B>,
+}
+
+/// Reads little-endian fields in order, as checkpoints and force laws are encoded.
+pub(crate) struct Reader<'a> {
+    pub(crate) bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn take<const N: usize>(&mut self) -> Result<[u8; N], CheckpointError> {
+        if self.bytes.len() < N {
+            return Err(CheckpointError::Truncated);
+        }
+        let (field, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        Ok(field.try_into().unwrap())
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, CheckpointError> {
+        Ok(u8::from_le_bytes(self.take()?))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, CheckpointError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, CheckpointError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    pub(crate) fn i64(&mut self) -> Result<i64, CheckpointError> {
+        Ok(i64::from_le_bytes(self.take()?))
+    }
+
+    pub(crate) fn scalar<S: Scalar>(&mut self) -> Result<S, CheckpointError> {
+        S::from_scaled(self.i64()? as i128).map_err(|_| CheckpointError::Overflow)
+    }
+}
+
+fn push_scalar<S: Scalar>(bytes: &mut Vec<u8>, value: S) -> Result<(), SimError> {
+    let raw = i64::try_from(value.to_scaled()).map_err(|_| SimError::Overflow)?;
+    bytes.extend_from_slice(&raw.to_le_bytes());
+    Ok(())
+}
+
+impl<B: Body> Checkpoint<B> {
+    pub fn encode(&self) -> Result<Vec<u8>, SimError> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&MAGIC);
+        bytes.push(CHECKPOINT_VERSION);
+        bytes.push(B::DIMENSIONS as u8);
+        bytes.extend_from_slice(&B::Scalar::SCALE.to_le_bytes());
+        bytes.extend_from_slice(&self.params.grav_g.to_le_bytes());
+        bytes.extend_from_slice(&self.params.time_period_sec.to_le_bytes());
+        bytes.extend_from_slice(&self.params.softening.to_le_bytes());
+        bytes.push(self.params.scheme.id());
+        let force = self.force.encode();
+        let length = u32::try_from(force.len()).map_err(|_| SimError::Overflow)?;
+        bytes.extend_from_slice(&length.to_le_bytes());
+        bytes.extend_from_slice(&force);
+        bytes.extend_from_slice(&self.tick.to_le_bytes());
+
+        let bodies = u32::try_from(self.system.len()).map_err(|_| SimError::Overflow)?;
+        bytes.extend_from_slice(&bodies.to_le_bytes());
+        for state in &self.system {
+            bytes.extend_from_slice(&state.get_id().to_le_bytes());
+            bytes.extend_from_slice(&state.get_mass().to_le_bytes());
+            bytes.extend_from_slice(&state.get_radius().to_le_bytes());
+            bytes.push(state.get_kind().id());
+            for value in &state.get_position()[..B::DIMENSIONS] {
+                push_scalar(&mut bytes, *value)?;
+            }
+            for value in &state.get_velocity()[..B::DIMENSIONS] {
+                push_scalar(&mut bytes, *value)?;
+            }
+        }
+        Ok(bytes)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, CheckpointError> {
+        let mut reader = Reader { bytes };
+        if reader.take()? != MAGIC {
+            return Err(CheckpointError::NotACheckpoint);
+        }
+        let version = reader.u8()?;
+        if version != CHECKPOINT_VERSION {
+            return Err(CheckpointError::UnsupportedVersion(version));
+        }
+        let dimensions = reader.u8()?;
+        if dimensions as usize != B::DIMENSIONS {
+            return Err(CheckpointError::WrongDimensions(dimensions));
+        }
+        let scale = reader.i64()?;
+        if scale != B::Scalar::SCALE {
+            return Err(CheckpointError::WrongScale(scale));
+        }
+
+        let grav_g = reader.i64()?;
+        let time_period_sec = reader.i64()?;
+        let softening = reader.i64()?;
+        let scheme = reader.u8()?;
+        let params = PhysicsParams {
+            grav_g,
+            time_period_sec,
+            softening,
+            scheme: Scheme::from_id(scheme).ok_or(CheckpointError::UnknownScheme(scheme))?,
+        };
+        let length = reader.u32()? as usize;
+        if reader.bytes.len() < length {
+            return Err(CheckpointError::Truncated);
+        }
+        let (force, rest) = reader.bytes.split_at(length);
+        reader.bytes = rest;
+        let force = ForceLaw::decode(force).ok_or(CheckpointError::UnknownForce)?;
+        let tick = reader.u32()?;
+
+        let bodies = reader.u32()?;
+        let mut system = Vec::new();
+        for _ in 0..bodies {
+            let id = reader.u32()?;
+            let mass = reader.u64()?;
+            let radius = reader.u64()?;
+            let kind = reader.u8()?;
+            let kind = BodyKind::from_id(kind).ok_or(CheckpointError::UnknownKind(kind))?;
+            let mut position = [B::Scalar::ZERO; 3];
+            let mut velocity = [B::Scalar::ZERO; 3];
+            for value in &mut position[..B::DIMENSIONS] {
+                *value = reader.scalar()?;
+            }
+            for value in &mut velocity[..B::DIMENSIONS] {
+                *value = reader.scalar()?;
+            }
+            system.push(B::from_parts(id, mass, radius, position, velocity).with_kind(kind));
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(CheckpointError::TrailingBytes);
+        }
+        Ok(Checkpoint {
+            params,
+            force,
+            tick,
+            system,
+        })
+    }
+
+    /// Carries the run on for another `ticks` ticks, under its force law.
+    pub fn resume(&self, ticks: u32) -> Result<Checkpoint<B>, SimError> {
+        Ok(Checkpoint {
+            params: self.params,
+            force: self.force.clone(),
+            tick: self.tick.checked_add(ticks).ok_or(SimError::Overflow)?,
+            system: tick_many_force(&self.params, &self.force, ticks, &self.system)?,
+        })
+    }
+}
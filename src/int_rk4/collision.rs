@@ -0,0 +1,175 @@
+use super::{Body, BodyKind, PhysicsParams, Scalar, SimError};
+
+use core::cmp::Ordering;
+
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// Bodies whose spheres touch merge into one, as if they stuck together. The merged
+// body sits at the centre of mass of the pair and moves with their combined momentum,
+// so mass and momentum are conserved while the kinetic energy lost in the impact is
+// not. Its volume, or area for planar bodies, is the sum of theirs. An anchor takes in
+// whatever hits it without moving, and two tracers merge into a tracer.
+
+/// The merger of body `other_id` into body `id` at the end of `tick`. `merged` is
+/// the resulting body, which keeps the id `id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collision<B> {
+    pub tick: u32,
+    pub id: u32,
+    pub other_id: u32,
+    pub merged: B,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollisionRun<B> {
+    pub system: Vec<B>,
+    /// The mergers in the order they happened.
+    pub collisions: Vec<Collision<B>>,
+}
+
+/// The largest integer whose `n`th power is at most `x`.
+fn root(x: u128, n: u32) -> u128 {
+    if n == 1 {
+        return x;
+    }
+
+    // low^n <= x < high^n, where high^n may not fit in a u128
+    let mut low: u128 = 0;
+    let mut high: u128 = 1 << (128 / n);
+    while high - low > 1 {
+        let mid = (low + high) / 2;
+        match mid.checked_pow(n) {
+            Some(power) if power <= x => low = mid,
+            _ => high = mid,
+        }
+    }
+    low
+}
+
+fn touching<B: Body>(state: &B, other_state: &B) -> Result<bool, SimError> {
+    let position = state.get_position();
+    let other_position = other_state.get_position();
+    let mut d = [B::Scalar::ZERO; 3];
+    for axis in 0..B::DIMENSIONS {
+        d[axis] = position[axis].checked_sub(other_position[axis])?;
+    }
+
+    let reach =
+        B::Scalar::from_scaled(state.get_radius() as i128 + other_state.get_radius() as i128)?;
+    Ok(B::Scalar::cmp_norm(&d[..B::DIMENSIONS], reach) != Ordering::Greater)
+}
+
+/// Merges two bodies into one that keeps the id of `state`.
+pub fn merge<B: Body>(state: &B, other_state: &B) -> Result<B, SimError> {
+    let (kind, other_kind) = (state.get_kind(), other_state.get_kind());
+    let (mut mass, mut other_mass) = match (kind, other_kind) {
+        // the merger stays where the anchor is
+        (BodyKind::Anchor, _) => (1, 0),
+        (_, BodyKind::Anchor) => (0, 1),
+        _ => (state.get_mass() as i128, other_state.get_mass() as i128),
+    };
+    if mass + other_mass == 0 {
+        // massless bodies meet half way
+        mass = 1;
+        other_mass = 1;
+    }
+
+    let mut position = state.get_position();
+    let mut velocity = state.get_velocity();
+    let other_position = other_state.get_position();
+    let other_velocity = other_state.get_velocity();
+    for axis in 0..B::DIMENSIONS {
+        position[axis] = B::Scalar::weighted_sum(
+            [(mass, position[axis]), (other_mass, other_position[axis])],
+            mass + other_mass,
+        )?;
+        velocity[axis] = B::Scalar::weighted_sum(
+            [(mass, velocity[axis]), (other_mass, other_velocity[axis])],
+            mass + other_mass,
+        )?;
+    }
+
+    let dimensions = B::DIMENSIONS as u32;
+    let volume = (state.get_radius() as u128)
+        .saturating_pow(dimensions)
+        .saturating_add((other_state.get_radius() as u128).saturating_pow(dimensions));
+
+    let merged_mass = state
+        .get_mass()
+        .checked_add(other_state.get_mass())
+        .ok_or(SimError::Overflow)?;
+    let merged_radius = u64::try_from(root(volume, dimensions)).map_err(|_| SimError::Overflow)?;
+
+    let merged_kind = match (kind, other_kind) {
+        (BodyKind::Anchor, _) | (_, BodyKind::Anchor) => BodyKind::Anchor,
+        (BodyKind::Tracer, BodyKind::Tracer) => BodyKind::Tracer,
+        _ => BodyKind::Free,
+    };
+
+    Ok(state
+        .with_motion(position, velocity)
+        .with_mass(merged_mass, merged_radius)
+        .with_kind(merged_kind))
+}
+
+fn find_collision<B: Body>(system: &[B]) -> Result<Option<(usize, usize)>, SimError> {
+    for i in 0..system.len() {
+        for j in i + 1..system.len() {
+            if touching(&system[i], &system[j])? {
+                return Ok(Some((i, j)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Merges touching bodies until none are left. Each merger takes the place of the
+/// first body of the pair, so the system keeps its order.
+pub fn merge_collisions<B: Body>(tick: u32, system: &[B]) -> Result<CollisionRun<B>, SimError> {
+    let mut next_system = system.to_vec();
+    let mut collisions = Vec::new();
+
+    while let Some((i, j)) = find_collision(&next_system)? {
+        let merged = merge(&next_system[i], &next_system[j])?;
+        collisions.push(Collision {
+            tick,
+            id: next_system[i].get_id(),
+            other_id: next_system[j].get_id(),
+            merged: merged.clone(),
+        });
+        next_system[i] = merged;
+        next_system.remove(j);
+    }
+
+    Ok(CollisionRun {
+        system: next_system,
+        collisions,
+    })
+}
+
+/// Advances `system` by one step and merges the bodies that then touch, `tick`
+/// being the number of the step.
+pub fn tick_merging<B: Body>(
+    params: &PhysicsParams,
+    tick: u32,
+    system: &[B],
+) -> Result<CollisionRun<B>, SimError> {
+    merge_collisions(tick, &super::tick(params, system)?)
+}
+
+/// `tick_many` with colliding bodies merged. Bodies touching from the start are
+/// merged at tick 0, before the first step.
+pub fn tick_many_merging<B: Body>(
+    params: &PhysicsParams,
+    ticks: u32,
+    system: &[B],
+) -> Result<CollisionRun<B>, SimError> {
+    let mut run = merge_collisions(0, system)?;
+    for tick in 1..=ticks {
+        let next_run = tick_merging(params, tick, &run.system)?;
+        run.system = next_run.system;
+        run.collisions.extend(next_run.collisions);
+    }
+    Ok(run)
+}
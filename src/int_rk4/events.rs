@@ -0,0 +1,280 @@
+use super::{tick_force, Body, BodyKind, ForceModel, PhysicsParams, Scalar, SimError};
+
+use core::cmp::Ordering;
+
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// Events mark the moments of a run worth pointing out: a pair of bodies coming close, a
+// body breaking free of the others and a body passing the nearest or farthest point of
+// its orbit. Each is found by comparing an observation of the system with that of the
+// tick before, so an event at tick t happened during the step from t - 1 to t and none
+// are reported at tick 0.
+//
+// The orbit of a body is taken about the centre of mass of the other bodies, as if they
+// were a single body moving with their total momentum. The body is unbound when its
+// energy in that two-body problem is positive, that is when v^2 / 2 > G (m + M) / r,
+// the distance r softened as the gravity of the run is. It passes periapsis when it
+// stops falling towards the centre and rises away from it, and apoapsis the other way
+// about. Tracers orbit like any other body but are not among the others, as they pull
+// nothing, and anchors never move and so have no orbit. Escapes are judged by gravity
+// whatever the force law of the run.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "u8", into = "u8")]
+pub enum EventKind {
+    /// Two bodies came closer than `EventDetector::close_approach`.
+    CloseApproach,
+    /// A body bound to the others became unbound.
+    Escape,
+    Periapsis,
+    Apoapsis,
+}
+
+impl EventKind {
+    pub fn from_id(id: u8) -> Option<EventKind> {
+        match id {
+            0 => Some(EventKind::CloseApproach),
+            1 => Some(EventKind::Escape),
+            2 => Some(EventKind::Periapsis),
+            3 => Some(EventKind::Apoapsis),
+            _ => None,
+        }
+    }
+
+    pub fn id(&self) -> u8 {
+        match self {
+            EventKind::CloseApproach => 0,
+            EventKind::Escape => 1,
+            EventKind::Periapsis => 2,
+            EventKind::Apoapsis => 3,
+        }
+    }
+}
+
+impl TryFrom<u8> for EventKind {
+    type Error = &'static str;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        EventKind::from_id(id).ok_or("unknown event kind")
+    }
+}
+
+impl From<EventKind> for u8 {
+    fn from(kind: EventKind) -> u8 {
+        kind.id()
+    }
+}
+
+/// An event of body `id` at the end of `tick`. `other_id` is the other body of a close
+/// approach, and `id` itself for the events of a single body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    pub tick: u32,
+    pub kind: EventKind,
+    pub id: u32,
+    pub other_id: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventRun<B> {
+    pub system: Vec<B>,
+    /// The events in the order they happened, those of a tick in the order of the
+    /// bodies.
+    pub events: Vec<Event>,
+}
+
+/// The events to look out for. The default looks out for none.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventDetector {
+    /// The distance, scaled like the bodies, below which a pair of bodies is reported
+    /// as a close approach. 0 reports no close approaches.
+    pub close_approach: i64,
+    pub escapes: bool,
+    /// Periapsis and apoapsis passes.
+    pub apsides: bool,
+}
+
+/// What an `EventDetector` knows of a system, to compare with the system a tick later.
+#[derive(Debug, Clone, Default)]
+pub struct Observation {
+    /// The ids of the pairs of bodies closer than `close_approach`.
+    close: Vec<(u32, u32)>,
+    /// The orbit of each body that has one, by id.
+    orbits: Vec<(u32, Orbit)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Orbit {
+    /// The sign of the radial velocity, `Less` while falling towards the centre.
+    radial: Ordering,
+    bound: bool,
+}
+
+/// The orbit of `state` about the centre of mass of the other bodies of `system`, `None`
+/// for an anchor or a body with nothing massive to orbit.
+fn orbit<B: Body>(
+    params: &PhysicsParams,
+    state: &B,
+    system: &[B],
+) -> Result<Option<Orbit>, SimError> {
+    if state.get_kind() == BodyKind::Anchor {
+        return Ok(None);
+    }
+
+    let others: Vec<&B> = system
+        .iter()
+        .filter(|s| s.get_id() != state.get_id() && s.get_kind() != BodyKind::Tracer)
+        .collect();
+    let others_mass = others
+        .iter()
+        .try_fold(0u64, |mass, s| mass.checked_add(s.get_mass()))
+        .ok_or(SimError::Overflow)?;
+    if others_mass == 0 {
+        return Ok(None);
+    }
+
+    // the offset from the centre with the softening ahead of the axes, so that its norm
+    // is the softened distance
+    let position = state.get_position();
+    let velocity = state.get_velocity();
+    let mut d = [B::Scalar::from_scaled(params.softening as i128)?; 4];
+    let mut radial = B::Scalar::ZERO;
+    let mut speed_squared = B::Scalar::ZERO;
+    for axis in 0..B::DIMENSIONS {
+        let centre = B::Scalar::weighted_sum(
+            others
+                .iter()
+                .map(|s| (s.get_mass() as i128, s.get_position()[axis])),
+            others_mass as i128,
+        )?;
+        let centre_velocity = B::Scalar::weighted_sum(
+            others
+                .iter()
+                .map(|s| (s.get_mass() as i128, s.get_velocity()[axis])),
+            others_mass as i128,
+        )?;
+        d[axis + 1] = position[axis].checked_sub(centre)?;
+        let v = velocity[axis].checked_sub(centre_velocity)?;
+        radial = radial.checked_add(d[axis + 1].checked_mul(v)?)?;
+        speed_squared = speed_squared.checked_add(v.checked_mul(v)?)?;
+    }
+
+    // bound while v^2 r <= 2 G (m + M), a tracer adding no mass of its own
+    let r = B::Scalar::norm(&d[..=B::DIMENSIONS])?;
+    let mut mass = others_mass as i128;
+    if state.get_kind() != BodyKind::Tracer {
+        mass += state.get_mass() as i128;
+    }
+    let reach = B::Scalar::from_scaled(params.grav_g as i128)?
+        .checked_mul(B::Scalar::from_scaled(mass)?)?
+        .checked_mul_int(2)?;
+    Ok(Some(Orbit {
+        radial: radial
+            .partial_cmp(&B::Scalar::ZERO)
+            .unwrap_or(Ordering::Equal),
+        bound: speed_squared.checked_mul(r)? <= reach,
+    }))
+}
+
+impl EventDetector {
+    pub fn observe<B: Body>(
+        &self,
+        params: &PhysicsParams,
+        system: &[B],
+    ) -> Result<Observation, SimError> {
+        let mut observation = Observation::default();
+
+        if self.close_approach > 0 {
+            let close_approach = B::Scalar::from_scaled(self.close_approach as i128)?;
+            for (i, state) in system.iter().enumerate() {
+                let position = state.get_position();
+                for other_state in &system[i + 1..] {
+                    let other_position = other_state.get_position();
+                    let mut d = [B::Scalar::ZERO; 3];
+                    for axis in 0..B::DIMENSIONS {
+                        d[axis] = position[axis].checked_sub(other_position[axis])?;
+                    }
+                    if B::Scalar::cmp_norm(&d[..B::DIMENSIONS], close_approach) == Ordering::Less {
+                        observation
+                            .close
+                            .push((state.get_id(), other_state.get_id()));
+                    }
+                }
+            }
+        }
+
+        if self.escapes || self.apsides {
+            for state in system {
+                if let Some(orbit) = orbit(params, state, system)? {
+                    observation.orbits.push((state.get_id(), orbit));
+                }
+            }
+        }
+        Ok(observation)
+    }
+
+    /// The events of `tick`, between the observations `before` and `after` of the
+    /// system at the ticks either side of its step.
+    pub fn events(&self, tick: u32, before: &Observation, after: &Observation) -> Vec<Event> {
+        let mut events = Vec::new();
+        let mut push = |kind, id, other_id| {
+            events.push(Event {
+                tick,
+                kind,
+                id,
+                other_id,
+            })
+        };
+
+        for &(id, other_id) in &after.close {
+            if !before.close.contains(&(id, other_id)) {
+                push(EventKind::CloseApproach, id, other_id);
+            }
+        }
+
+        for &(id, orbit) in &after.orbits {
+            let Some(&(_, previous)) = before.orbits.iter().find(|(i, _)| *i == id) else {
+                continue;
+            };
+            if self.escapes && previous.bound && !orbit.bound {
+                push(EventKind::Escape, id, id);
+            }
+            if self.apsides {
+                match (previous.radial, orbit.radial) {
+                    (Ordering::Less, Ordering::Equal | Ordering::Greater) => {
+                        push(EventKind::Periapsis, id, id)
+                    }
+                    (Ordering::Greater, Ordering::Equal | Ordering::Less) => {
+                        push(EventKind::Apoapsis, id, id)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        events
+    }
+}
+
+/// `tick_many_force` with the events `detector` looks out for reported tick by tick.
+pub fn tick_many_events<B: Body, F: ForceModel>(
+    params: &PhysicsParams,
+    force: &F,
+    detector: &EventDetector,
+    ticks: u32,
+    system: &[B],
+) -> Result<EventRun<B>, SimError> {
+    let mut next_system = system.to_vec();
+    let mut observation = detector.observe(params, &next_system)?;
+    let mut events = Vec::new();
+    for tick in 1..=ticks {
+        next_system = tick_force(params, force, &next_system)?;
+        let next_observation = detector.observe(params, &next_system)?;
+        events.extend(detector.events(tick, &observation, &next_observation));
+        observation = next_observation;
+    }
+    Ok(EventRun {
+        system: next_system,
+        events,
+    })
+}
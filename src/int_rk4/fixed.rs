@@ -0,0 +1,175 @@
+use super::math::{div_sqrt, isqrt};
+use super::PRECISION;
+
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Reasons a simulation can fail part way through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SimError {
+    /// A value left the range of the fixed-point type.
+    Overflow,
+    /// Two bodies met with no softening to keep them apart.
+    ZeroDistance,
+    /// A square root of a negative number was taken.
+    NegativeSqrt,
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::Overflow => write!(f, "fixed-point overflow"),
+            SimError::ZeroDistance => write!(f, "zero distance between bodies"),
+            SimError::NegativeSqrt => write!(f, "square root of a negative number"),
+        }
+    }
+}
+
+/// A fixed-point number, held as an i64 scaled by `SCALE`.
+///
+/// The checked operations fail with `SimError::Overflow` where the result does not
+/// fit, dividing by zero included. Products are formed in i128 whenever they
+/// overflow i64, so a product that is divided back into range is still exact and
+/// rounds the same way, towards zero, as plain i64 arithmetic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FixedPoint<const SCALE: i64>(i64);
+
+/// The scale tokens are minted with unless they record another.
+pub type Fixed = FixedPoint<PRECISION>;
+
+/// `a * b / c`, only going through i128 when `a * b` overflows i64.
+fn mul_div_raw(a: i64, b: i64, c: i64) -> Result<i64, SimError> {
+    if c == 0 {
+        return Err(SimError::Overflow);
+    }
+
+    match a.checked_mul(b) {
+        Some(product) => product.checked_div(c).ok_or(SimError::Overflow),
+        None => i64::try_from(a as i128 * b as i128 / c as i128).map_err(|_| SimError::Overflow),
+    }
+}
+
+/// The root Heron's method gives, see
+/// https://en.wikipedia.org/wiki/Methods_of_computing_square_roots#Heron's_method
+///
+/// NOTE: tokens were minted with Heron's method returning its last iterate rather than
+/// the one before it, which is one above the floor of the root whenever `x + 1` is a
+/// square. Kept as-is so that tokens replay identically, but found from `isqrt` so
+/// that the number of iterations is bounded.
+pub(crate) fn sqrt_heron(x: i128) -> Result<i128, SimError> {
+    if x < 0 {
+        return Err(SimError::NegativeSqrt);
+    }
+
+    let x = x as u128;
+    let root = isqrt(x);
+    if x > 0 && x + 1 == (root + 1) * (root + 1) {
+        return Ok(root as i128 + 1);
+    }
+    Ok(root as i128)
+}
+
+impl<const SCALE: i64> FixedPoint<SCALE> {
+    pub const ZERO: Self = FixedPoint(0);
+    pub const ONE: Self = FixedPoint(SCALE);
+    pub const MAX: Self = FixedPoint(i64::MAX);
+    pub const MIN: Self = FixedPoint(i64::MIN);
+
+    /// A value from its representation, so `from_raw(SCALE)` is one.
+    pub const fn from_raw(raw: i64) -> Self {
+        FixedPoint(raw)
+    }
+
+    pub fn from_wide(raw: i128) -> Result<Self, SimError> {
+        i64::try_from(raw)
+            .map(FixedPoint)
+            .map_err(|_| SimError::Overflow)
+    }
+
+    pub const fn raw(self) -> i64 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Self) -> Result<Self, SimError> {
+        self.0
+            .checked_add(other.0)
+            .map(FixedPoint)
+            .ok_or(SimError::Overflow)
+    }
+
+    pub fn checked_sub(self, other: Self) -> Result<Self, SimError> {
+        self.0
+            .checked_sub(other.0)
+            .map(FixedPoint)
+            .ok_or(SimError::Overflow)
+    }
+
+    pub fn checked_neg(self) -> Result<Self, SimError> {
+        self.0
+            .checked_neg()
+            .map(FixedPoint)
+            .ok_or(SimError::Overflow)
+    }
+
+    pub fn checked_mul(self, other: Self) -> Result<Self, SimError> {
+        mul_div_raw(self.0, other.0, SCALE).map(FixedPoint)
+    }
+
+    pub fn checked_div(self, other: Self) -> Result<Self, SimError> {
+        mul_div_raw(self.0, SCALE, other.0).map(FixedPoint)
+    }
+
+    /// `self * numerator / denominator` with a single rounding.
+    pub fn mul_div(self, numerator: Self, denominator: Self) -> Result<Self, SimError> {
+        mul_div_raw(self.0, numerator.0, denominator.0).map(FixedPoint)
+    }
+
+    pub fn checked_mul_int(self, n: i64) -> Result<Self, SimError> {
+        self.0
+            .checked_mul(n)
+            .map(FixedPoint)
+            .ok_or(SimError::Overflow)
+    }
+
+    pub fn checked_div_int(self, n: i64) -> Result<Self, SimError> {
+        self.0
+            .checked_div(n)
+            .map(FixedPoint)
+            .ok_or(SimError::Overflow)
+    }
+
+    pub fn checked_sqrt(self) -> Result<Self, SimError> {
+        Self::from_wide(sqrt_heron(self.0 as i128 * SCALE as i128)?)
+    }
+
+    /// One over the square root, rounded down.
+    pub fn checked_rsqrt(self) -> Result<Self, SimError> {
+        if self.0 < 0 {
+            return Err(SimError::NegativeSqrt);
+        }
+
+        // SCALE / sqrt(self / SCALE) is SCALE² / sqrt(self * SCALE)
+        let scale = SCALE as u128;
+        let root = div_sqrt(scale * scale, self.0 as u128 * scale).ok_or(SimError::Overflow)?;
+        i64::try_from(root)
+            .map(FixedPoint)
+            .map_err(|_| SimError::Overflow)
+    }
+
+    pub fn saturating_add(self, other: Self) -> Self {
+        FixedPoint(self.0.saturating_add(other.0))
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        FixedPoint(self.0.saturating_sub(other.0))
+    }
+
+    pub fn saturating_mul(self, other: Self) -> Self {
+        match self.checked_mul(other) {
+            Ok(product) => product,
+            Err(_) if (self.0 < 0) == (other.0 < 0) => Self::MAX,
+            Err(_) => Self::MIN,
+        }
+    }
+}
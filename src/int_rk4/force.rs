@@ -0,0 +1,593 @@
+use super::checkpoint::Reader;
+use super::{
+    add_gravity, add_gravity_pairwise, rate_of, tick_per_body_into, tick_with_into,
+    without_tracers, Body, BodyKind, PhysicsParams, Scalar, SimError,
+};
+
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// A force model gives the acceleration of every body, so the integrators can drive
+// other interaction laws than gravity. `Gravity` is the law tokens have always been
+// minted with, the others are for collections with other physics. Models add up, a
+// pair of models acting as their sum. Coefficients are scaled like the bodies, as in
+// `PhysicsParams`, and the gravitational constant and softening of the params are used
+// by the gravitational models.
+//
+// A token records its model as a `ForceLaw`, encoded in bytes with every integer
+// little-endian, as checkpoints are. The first byte gives the law:
+//
+//   0  Gravity
+//   1  PowerLaw  exponent u8
+//   2  Coulomb   coulomb_k i64, then a u32 count of charges and an i64 for each
+//   3  Springs   a u32 count of springs, then for each the ids of its bodies as u32
+//                and its stiffness and rest length as i64
+//   4  Drag      coefficient i64
+//   5  Sum       a u32 count of laws, then each law, none of them a sum itself
+//
+// No bytes at all are taken as gravity, the law of every token that records none.
+
+/// Accelerations of the bodies of a system under some interaction law. Models need only
+/// sum the pulls of the bodies they are handed, the rate functions keeping tracers from
+/// pulling and anchors from moving, see `BodyKind`.
+pub trait ForceModel {
+    /// Adds to `acc` the acceleration of `state` under the model, against the other
+    /// bodies of `system`, those with the same id as `state` left out.
+    fn add_acceleration<B: Body>(
+        &self,
+        acc: &mut [B::Scalar; 3],
+        state: &B,
+        system: &[B],
+        params: &PhysicsParams,
+    ) -> Result<(), SimError>;
+
+    /// `add_acceleration` for every body of `system`, `accs` being in the same order.
+    fn add_accelerations<B: Body>(
+        &self,
+        accs: &mut [[B::Scalar; 3]],
+        system: &[B],
+        params: &PhysicsParams,
+    ) -> Result<(), SimError> {
+        for (acc, state) in accs.iter_mut().zip(system) {
+            self.add_acceleration(acc, state, system, params)?;
+        }
+        Ok(())
+    }
+
+    /// Rates of change of `state` against `system`.
+    fn rate_func<B: Body>(
+        &self,
+        state: &B,
+        system: &[B],
+        params: &PhysicsParams,
+    ) -> Result<B, SimError> {
+        let mut acc = [B::Scalar::ZERO; 3];
+        if state.get_kind() != BodyKind::Anchor {
+            let sources = without_tracers(system);
+            let sources = sources.as_deref().unwrap_or(system);
+            self.add_acceleration(&mut acc, state, sources, params)?;
+        }
+        Ok(rate_of(state, acc))
+    }
+
+    /// Rates of change of every body in `system`, each evaluated against that same
+    /// system.
+    fn rate_func_system<B: Body>(
+        &self,
+        system: &[B],
+        params: &PhysicsParams,
+    ) -> Result<Vec<B>, SimError> {
+        let mut accs = Vec::from_iter(system.iter().map(|_| [B::Scalar::ZERO; 3]));
+        if let Some(sources) = without_tracers(system) {
+            // the bodies that pull act on each other as a system of their own, and each
+            // tracer is then pulled by them alone
+            let mut source_accs = Vec::from_iter(sources.iter().map(|_| [B::Scalar::ZERO; 3]));
+            self.add_accelerations(&mut source_accs, &sources, params)?;
+            let mut source_accs = source_accs.into_iter();
+            for (acc, state) in accs.iter_mut().zip(system) {
+                match state.get_kind() {
+                    BodyKind::Tracer => self.add_acceleration(acc, state, &sources, params)?,
+                    _ => *acc = source_accs.next().unwrap_or(*acc),
+                }
+            }
+        } else {
+            self.add_accelerations(&mut accs, system, params)?;
+        }
+        Ok(system
+            .iter()
+            .zip(accs)
+            .map(|(state, acc)| rate_of(state, acc))
+            .collect())
+    }
+}
+
+/// Newtonian gravity as tokens have always been minted with it, see
+/// `grav_rate_func_params`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Gravity;
+
+/// Gravity falling off as the `exponent` power of the distance rather than its square.
+/// An exponent of 2 is Newtonian gravity, rounded as `Scalar::mul_div_norm_cubed` does
+/// rather than as tokens minted with `Gravity` are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerLaw {
+    pub exponent: u8,
+}
+
+/// Electrostatics, bodies of like charge repelling and unlike ones attracting with a
+/// force of `coulomb_k` q1 q2 / r^2, softened like gravity. `charges` holds the charge
+/// of each body by id, bodies without one being neutral. A body of no mass, such as a
+/// tracer, has no inertia for the force to act on and is left to gravity.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Coulomb {
+    pub coulomb_k: i64,
+    pub charges: Vec<i64>,
+}
+
+/// A Hooke spring between the bodies with ids `a` and `b`, pulling them together with
+/// a force of `stiffness` times its extension past `rest_length`, or pushing them apart
+/// when compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Spring {
+    pub a: u32,
+    pub b: u32,
+    pub stiffness: i64,
+    pub rest_length: i64,
+}
+
+/// Springs between given pairs of bodies. A spring whose body is missing from the
+/// system, say merged away, does nothing, and a body of no mass is not moved by its
+/// springs, as with `Coulomb`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Springs {
+    pub springs: Vec<Spring>,
+}
+
+/// Linear drag, slowing every body by `coefficient` times its velocity whatever its
+/// mass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Drag {
+    pub coefficient: i64,
+}
+
+/// The force model a token is minted with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ForceLaw {
+    Gravity,
+    PowerLaw(PowerLaw),
+    Coulomb(Coulomb),
+    Springs(Springs),
+    Drag(Drag),
+    Sum(Vec<ForceLaw>),
+}
+
+/// The offset of `position` from `other_position`, with the softening as an extra
+/// component ahead of the axes so that the norm of all of them is the softened
+/// distance. Fails with `SimError::ZeroDistance` when that distance is zero.
+fn offset<B: Body>(
+    position: &[B::Scalar; 3],
+    other_position: &[B::Scalar; 3],
+    softening: B::Scalar,
+) -> Result<[B::Scalar; 4], SimError> {
+    let mut components = [softening; 4];
+    for axis in 0..B::DIMENSIONS {
+        components[axis + 1] = position[axis].checked_sub(other_position[axis])?;
+    }
+    if components[..=B::DIMENSIONS]
+        .iter()
+        .all(|&c| c == B::Scalar::ZERO)
+    {
+        return Err(SimError::ZeroDistance);
+    }
+    Ok(components)
+}
+
+impl ForceModel for Gravity {
+    fn add_acceleration<B: Body>(
+        &self,
+        acc: &mut [B::Scalar; 3],
+        state: &B,
+        system: &[B],
+        params: &PhysicsParams,
+    ) -> Result<(), SimError> {
+        add_gravity(acc, state, system, params)
+    }
+
+    /// Pairwise, see `grav_rate_func_system_params`.
+    fn add_accelerations<B: Body>(
+        &self,
+        accs: &mut [[B::Scalar; 3]],
+        system: &[B],
+        params: &PhysicsParams,
+    ) -> Result<(), SimError> {
+        add_gravity_pairwise(accs, system, params)
+    }
+}
+
+impl ForceModel for PowerLaw {
+    fn add_acceleration<B: Body>(
+        &self,
+        acc: &mut [B::Scalar; 3],
+        state: &B,
+        system: &[B],
+        params: &PhysicsParams,
+    ) -> Result<(), SimError> {
+        let position = state.get_position();
+        let grav_g = B::Scalar::from_scaled(params.grav_g as i128)?;
+        let softening = B::Scalar::from_scaled(params.softening as i128)?;
+
+        for s in system {
+            if s.get_id() == state.get_id() {
+                continue;
+            }
+
+            let components = offset::<B>(&position, &s.get_position(), softening)?;
+            let components = &components[..=B::DIMENSIONS];
+            let pull = B::Scalar::from_scaled(s.get_mass() as i128)?
+                .checked_mul(grav_g)?
+                .checked_mul_int(-1)?;
+            // the pull along an axis is d / r^(exponent + 1), the inverse square
+            // law taking r^3 from the squared distance in one go
+            let r = B::Scalar::norm(components)?;
+            for axis in 0..B::DIMENSIONS {
+                let d = components[axis + 1];
+                let mut a = match self.exponent {
+                    0 => pull.mul_div(d, r)?,
+                    1 => pull.mul_div(d, r)?.checked_div(r)?,
+                    _ => pull.mul_div_norm_cubed(d, components)?,
+                };
+                for _ in 2..self.exponent {
+                    a = a.checked_div(r)?;
+                }
+                acc[axis] = acc[axis].checked_add(a)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Coulomb {
+    fn charge(&self, id: u32) -> i64 {
+        self.charges.get(id as usize).copied().unwrap_or(0)
+    }
+}
+
+impl ForceModel for Coulomb {
+    fn add_acceleration<B: Body>(
+        &self,
+        acc: &mut [B::Scalar; 3],
+        state: &B,
+        system: &[B],
+        params: &PhysicsParams,
+    ) -> Result<(), SimError> {
+        let charge = self.charge(state.get_id());
+        if charge == 0 || state.get_mass() == 0 {
+            return Ok(());
+        }
+
+        let position = state.get_position();
+        let softening = B::Scalar::from_scaled(params.softening as i128)?;
+        // k q / m of the body itself, to be multiplied by the charge of each other body
+        let coupling = B::Scalar::from_scaled(charge as i128)?
+            .checked_mul(B::Scalar::from_scaled(self.coulomb_k as i128)?)?
+            .checked_div(B::Scalar::from_scaled(state.get_mass() as i128)?)?;
+
+        for s in system {
+            let other_charge = self.charge(s.get_id());
+            if s.get_id() == state.get_id() || other_charge == 0 {
+                continue;
+            }
+
+            // like charges push the body away from the other, along its offset
+            let components = offset::<B>(&position, &s.get_position(), softening)?;
+            let components = &components[..=B::DIMENSIONS];
+            let push = coupling.checked_mul(B::Scalar::from_scaled(other_charge as i128)?)?;
+            for axis in 0..B::DIMENSIONS {
+                let a = push.mul_div_norm_cubed(components[axis + 1], components)?;
+                acc[axis] = acc[axis].checked_add(a)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ForceModel for Springs {
+    fn add_acceleration<B: Body>(
+        &self,
+        acc: &mut [B::Scalar; 3],
+        state: &B,
+        system: &[B],
+        _params: &PhysicsParams,
+    ) -> Result<(), SimError> {
+        if state.get_mass() == 0 {
+            return Ok(());
+        }
+        let id = state.get_id();
+        let position = state.get_position();
+
+        for spring in &self.springs {
+            let other_id = match (spring.a == id, spring.b == id) {
+                (true, false) => spring.b,
+                (false, true) => spring.a,
+                _ => continue,
+            };
+            let Some(other) = system.iter().find(|s| s.get_id() == other_id) else {
+                continue;
+            };
+
+            let components = offset::<B>(&position, &other.get_position(), B::Scalar::ZERO)?;
+            let components = &components[..=B::DIMENSIONS];
+            let r = B::Scalar::norm(components)?;
+            // -k (r - rest length) / m, along the unit offset d / r
+            let pull = r
+                .checked_sub(B::Scalar::from_scaled(spring.rest_length as i128)?)?
+                .checked_mul(B::Scalar::from_scaled(spring.stiffness as i128)?)?
+                .checked_div(B::Scalar::from_scaled(state.get_mass() as i128)?)?
+                .checked_mul_int(-1)?;
+            for axis in 0..B::DIMENSIONS {
+                let a = pull.mul_div(components[axis + 1], r)?;
+                acc[axis] = acc[axis].checked_add(a)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ForceModel for Drag {
+    fn add_acceleration<B: Body>(
+        &self,
+        acc: &mut [B::Scalar; 3],
+        state: &B,
+        _system: &[B],
+        _params: &PhysicsParams,
+    ) -> Result<(), SimError> {
+        let coefficient = B::Scalar::from_scaled(self.coefficient as i128)?;
+        let velocity = state.get_velocity();
+        for axis in 0..B::DIMENSIONS {
+            acc[axis] = acc[axis].checked_sub(velocity[axis].checked_mul(coefficient)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: ForceModel, G: ForceModel> ForceModel for (F, G) {
+    fn add_acceleration<B: Body>(
+        &self,
+        acc: &mut [B::Scalar; 3],
+        state: &B,
+        system: &[B],
+        params: &PhysicsParams,
+    ) -> Result<(), SimError> {
+        self.0.add_acceleration(acc, state, system, params)?;
+        self.1.add_acceleration(acc, state, system, params)
+    }
+
+    fn add_accelerations<B: Body>(
+        &self,
+        accs: &mut [[B::Scalar; 3]],
+        system: &[B],
+        params: &PhysicsParams,
+    ) -> Result<(), SimError> {
+        self.0.add_accelerations(accs, system, params)?;
+        self.1.add_accelerations(accs, system, params)
+    }
+}
+
+impl ForceModel for ForceLaw {
+    fn add_acceleration<B: Body>(
+        &self,
+        acc: &mut [B::Scalar; 3],
+        state: &B,
+        system: &[B],
+        params: &PhysicsParams,
+    ) -> Result<(), SimError> {
+        match self {
+            ForceLaw::Gravity => Gravity.add_acceleration(acc, state, system, params),
+            ForceLaw::PowerLaw(law) => law.add_acceleration(acc, state, system, params),
+            ForceLaw::Coulomb(law) => law.add_acceleration(acc, state, system, params),
+            ForceLaw::Springs(law) => law.add_acceleration(acc, state, system, params),
+            ForceLaw::Drag(law) => law.add_acceleration(acc, state, system, params),
+            ForceLaw::Sum(laws) => {
+                for law in laws {
+                    law.add_acceleration(acc, state, system, params)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn add_accelerations<B: Body>(
+        &self,
+        accs: &mut [[B::Scalar; 3]],
+        system: &[B],
+        params: &PhysicsParams,
+    ) -> Result<(), SimError> {
+        match self {
+            ForceLaw::Gravity => Gravity.add_accelerations(accs, system, params),
+            ForceLaw::PowerLaw(law) => law.add_accelerations(accs, system, params),
+            ForceLaw::Coulomb(law) => law.add_accelerations(accs, system, params),
+            ForceLaw::Springs(law) => law.add_accelerations(accs, system, params),
+            ForceLaw::Drag(law) => law.add_accelerations(accs, system, params),
+            ForceLaw::Sum(laws) => {
+                for law in laws {
+                    law.add_accelerations(accs, system, params)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<Gravity> for ForceLaw {
+    fn from(_: Gravity) -> Self {
+        ForceLaw::Gravity
+    }
+}
+
+impl From<PowerLaw> for ForceLaw {
+    fn from(law: PowerLaw) -> Self {
+        ForceLaw::PowerLaw(law)
+    }
+}
+
+impl From<Coulomb> for ForceLaw {
+    fn from(law: Coulomb) -> Self {
+        ForceLaw::Coulomb(law)
+    }
+}
+
+impl From<Springs> for ForceLaw {
+    fn from(law: Springs) -> Self {
+        ForceLaw::Springs(law)
+    }
+}
+
+impl From<Drag> for ForceLaw {
+    fn from(law: Drag) -> Self {
+        ForceLaw::Drag(law)
+    }
+}
+
+impl ForceLaw {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.encode_into(&mut bytes);
+        bytes
+    }
+
+    fn encode_into(&self, bytes: &mut Vec<u8>) {
+        match self {
+            ForceLaw::Gravity => bytes.push(0),
+            ForceLaw::PowerLaw(law) => bytes.extend_from_slice(&[1, law.exponent]),
+            ForceLaw::Coulomb(law) => {
+                bytes.push(2);
+                bytes.extend_from_slice(&law.coulomb_k.to_le_bytes());
+                bytes.extend_from_slice(&(law.charges.len() as u32).to_le_bytes());
+                for charge in &law.charges {
+                    bytes.extend_from_slice(&charge.to_le_bytes());
+                }
+            }
+            ForceLaw::Springs(law) => {
+                bytes.push(3);
+                bytes.extend_from_slice(&(law.springs.len() as u32).to_le_bytes());
+                for spring in &law.springs {
+                    bytes.extend_from_slice(&spring.a.to_le_bytes());
+                    bytes.extend_from_slice(&spring.b.to_le_bytes());
+                    bytes.extend_from_slice(&spring.stiffness.to_le_bytes());
+                    bytes.extend_from_slice(&spring.rest_length.to_le_bytes());
+                }
+            }
+            ForceLaw::Drag(law) => {
+                bytes.push(4);
+                bytes.extend_from_slice(&law.coefficient.to_le_bytes());
+            }
+            ForceLaw::Sum(laws) => {
+                bytes.push(5);
+                bytes.extend_from_slice(&(laws.len() as u32).to_le_bytes());
+                for law in laws {
+                    law.encode_into(bytes);
+                }
+            }
+        }
+    }
+
+    /// The law encoded in `bytes`, `None` where they hold no law or anything past it.
+    pub fn decode(bytes: &[u8]) -> Option<ForceLaw> {
+        if bytes.is_empty() {
+            return Some(ForceLaw::Gravity);
+        }
+
+        let mut reader = Reader { bytes };
+        let law = ForceLaw::decode_from(&mut reader, true)?;
+        reader.bytes.is_empty().then_some(law)
+    }
+
+    fn decode_from(reader: &mut Reader, summable: bool) -> Option<ForceLaw> {
+        Some(match reader.u8().ok()? {
+            0 => ForceLaw::Gravity,
+            1 => ForceLaw::PowerLaw(PowerLaw {
+                exponent: reader.u8().ok()?,
+            }),
+            2 => {
+                let coulomb_k = reader.i64().ok()?;
+                let mut charges = Vec::new();
+                for _ in 0..reader.u32().ok()? {
+                    charges.push(reader.i64().ok()?);
+                }
+                ForceLaw::Coulomb(Coulomb { coulomb_k, charges })
+            }
+            3 => {
+                let mut springs = Vec::new();
+                for _ in 0..reader.u32().ok()? {
+                    springs.push(Spring {
+                        a: reader.u32().ok()?,
+                        b: reader.u32().ok()?,
+                        stiffness: reader.i64().ok()?,
+                        rest_length: reader.i64().ok()?,
+                    });
+                }
+                ForceLaw::Springs(Springs { springs })
+            }
+            4 => ForceLaw::Drag(Drag {
+                coefficient: reader.i64().ok()?,
+            }),
+            5 if summable => {
+                let mut laws = Vec::new();
+                for _ in 0..reader.u32().ok()? {
+                    laws.push(ForceLaw::decode_from(reader, false)?);
+                }
+                ForceLaw::Sum(laws)
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// `tick` with the accelerations of `force` rather than gravity.
+pub fn tick_force<B: Body, F: ForceModel>(
+    params: &PhysicsParams,
+    force: &F,
+    system: &[B],
+) -> Result<Vec<B>, SimError> {
+    let mut next_system = system.to_vec();
+    tick_force_into(params, force, system, &mut next_system)?;
+    Ok(next_system)
+}
+
+/// `tick_force` writing the advanced system into `next_system`, which holds as many
+/// bodies as `system`.
+pub fn tick_force_into<B: Body, F: ForceModel>(
+    params: &PhysicsParams,
+    force: &F,
+    system: &[B],
+    next_system: &mut [B],
+) -> Result<(), SimError> {
+    tick_with_into(
+        params,
+        system,
+        |next_system| {
+            tick_per_body_into(
+                params.time_period_sec,
+                system,
+                force.rate_func_system(system, params)?,
+                |s| force.rate_func(s, system, params),
+                next_system,
+            )
+        },
+        |s| force.rate_func_system(s, params),
+        next_system,
+    )
+}
+
+pub fn tick_many_force<B: Body, F: ForceModel>(
+    params: &PhysicsParams,
+    force: &F,
+    ticks: u32,
+    system: &[B],
+) -> Result<Vec<B>, SimError> {
+    let mut next_system = system.to_vec();
+    for _ in 0..ticks {
+        next_system = tick_force(params, force, &next_system)?;
+    }
+    Ok(next_system)
+}
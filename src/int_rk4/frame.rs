@@ -0,0 +1,104 @@
+use super::{Body, BodyKind, Scalar, SimError};
+
+use alloc::vec::Vec;
+
+// A system whose bodies carry momentum in total drifts off as a whole, and one whose
+// centre of mass is away from the origin sits off centre. Moving every body by the same
+// position and velocity changes neither how the bodies move about each other nor their
+// masses, so a system can be moved into the frame of its centre of mass, where both are
+// zero up to rounding, before it is minted.
+//
+// The sums are taken exactly in i128 and divided by the total mass once, rounding towards
+// zero, and each body is then moved by exactly that much. What the division leaves over
+// stays in the sums of the result. Whole moves that cancel it would in general have to
+// move the bodies by different amounts, so it is left there. Tracers pull nothing and so
+// carry no weight, whatever their mass. Anchors never move and so fix the frame: a system
+// with one is only moved to put its centre of mass at the origin, and its velocities are
+// left as they are.
+
+/// The total mass of the bodies that pull the others.
+fn total_mass<B: Body>(system: &[B]) -> Result<u64, SimError> {
+    system
+        .iter()
+        .filter(|s| s.get_kind() != BodyKind::Tracer)
+        .try_fold(0u64, |mass, s| mass.checked_add(s.get_mass()))
+        .ok_or(SimError::Overflow)
+}
+
+/// The mass weighted mean of `values` over the bodies that pull the others.
+fn weighted_mean<B, F>(system: &[B], mass: u64, values: F) -> Result<[B::Scalar; 3], SimError>
+where
+    B: Body,
+    F: Fn(&B) -> [B::Scalar; 3],
+{
+    let mut mean = [B::Scalar::ZERO; 3];
+    for (axis, component) in mean.iter_mut().enumerate().take(B::DIMENSIONS) {
+        *component = B::Scalar::weighted_sum(
+            system
+                .iter()
+                .filter(|s| s.get_kind() != BodyKind::Tracer)
+                .map(|s| (s.get_mass() as i128, values(s)[axis])),
+            mass as i128,
+        )?;
+    }
+    Ok(mean)
+}
+
+/// The centre of mass of `system`, rounded towards zero, or `None` when nothing in it has
+/// any mass.
+pub fn centre_of_mass<B: Body>(system: &[B]) -> Result<Option<[B::Scalar; 3]>, SimError> {
+    let mass = total_mass(system)?;
+    if mass == 0 {
+        return Ok(None);
+    }
+    Ok(Some(weighted_mean(system, mass, |s| s.get_position())?))
+}
+
+/// The total momentum of `system` along each axis, exactly. Masses and velocities are both
+/// scaled, so the momentum is scaled twice over, by `SCALE` squared.
+pub fn total_momentum<B: Body>(system: &[B]) -> Result<[i128; 3], SimError> {
+    let mut momentum = [0i128; 3];
+    for state in system.iter().filter(|s| s.get_kind() != BodyKind::Tracer) {
+        let velocity = state.get_velocity();
+        for axis in 0..B::DIMENSIONS {
+            momentum[axis] = (state.get_mass() as i128)
+                .checked_mul(velocity[axis].to_scaled())
+                .and_then(|term| momentum[axis].checked_add(term))
+                .ok_or(SimError::Overflow)?;
+        }
+    }
+    Ok(momentum)
+}
+
+/// `system` moved into the frame of its centre of mass, with the centre of mass at the
+/// origin and, unless there are anchors, no momentum in total, up to rounding. Along each
+/// axis the mass weighted sum of the positions of the result is less than the total mass,
+/// so that its centre of mass rounds to zero without being exactly zero, and its momentum
+/// is likewise less than one unit of velocity times the total mass. Masses, radii, kinds
+/// and ids are unchanged, as is a system with no mass at all.
+pub fn centre_of_mass_frame<B: Body>(system: &[B]) -> Result<Vec<B>, SimError> {
+    let mass = total_mass(system)?;
+    if mass == 0 {
+        return Ok(system.to_vec());
+    }
+
+    let centre = weighted_mean(system, mass, |s| s.get_position())?;
+    let centre_velocity = if system.iter().any(|s| s.get_kind() == BodyKind::Anchor) {
+        [B::Scalar::ZERO; 3]
+    } else {
+        weighted_mean(system, mass, |s| s.get_velocity())?
+    };
+
+    system
+        .iter()
+        .map(|state| {
+            let mut position = state.get_position();
+            let mut velocity = state.get_velocity();
+            for axis in 0..B::DIMENSIONS {
+                position[axis] = position[axis].checked_sub(centre[axis])?;
+                velocity[axis] = velocity[axis].checked_sub(centre_velocity[axis])?;
+            }
+            Ok(state.with_motion(position, velocity))
+        })
+        .collect()
+}
@@ -0,0 +1,328 @@
+use super::{Body, Fixed, FixedPoint, MotionState, Scalar, SimError};
+
+use core::fmt;
+
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+// Orbital elements describe a body's motion about a primary as an ellipse. The semi-major
+// axis a and the eccentricity e give its size and shape. The argument of periapsis ω is
+// the angle from the x axis to the closest approach to the primary. An anomaly places
+// the body along the ellipse.
+//
+// Elements and states are converted as in the two-body problem, where the pair pulls with
+// the gravitational parameter μ = G (M + m). The pull of any other body is left out.
+// Orbits run anticlockwise unless they are retrograde. A retrograde orbit is the mirror
+// image of the prograde one, so its angles are measured clockwise from ω, which is still
+// anticlockwise from the x axis.
+//
+// Kepler's equation, M = E - e sin E, is solved for the eccentric anomaly E by Newton's
+// method. It runs a bounded number of iterations and stops early once an iteration
+// changes nothing.
+
+const KEPLER_ITERATIONS: u32 = 32;
+
+/// Reasons a body cannot be put on, or read off, an orbit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrbitError {
+    /// The orbit is not an ellipse. Either the eccentricity is outside [0, 1), the
+    /// semi-major axis is not positive, or the body is moving at escape velocity or
+    /// faster.
+    Unbound,
+    /// No body of this id has been built to orbit.
+    UnknownPrimary(u32),
+    Sim(SimError),
+}
+
+impl fmt::Display for OrbitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrbitError::Unbound => write!(f, "orbit is not bound"),
+            OrbitError::UnknownPrimary(id) => write!(f, "unknown primary {}", id),
+            OrbitError::Sim(err) => err.fmt(f),
+        }
+    }
+}
+
+impl From<SimError> for OrbitError {
+    fn from(err: SimError) -> Self {
+        OrbitError::Sim(err)
+    }
+}
+
+/// Where a body is along its orbit, as an angle from periapsis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Anomaly<S = Fixed> {
+    /// The angle a body moving at a steady rate would have swept since periapsis, in
+    /// the same time. It grows in proportion to time.
+    Mean(S),
+    /// The angle the body has swept about the primary since periapsis.
+    True(S),
+}
+
+/// An elliptic orbit about a primary, with lengths and angles in radians scaled like
+/// the bodies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrbitalElements<S = Fixed> {
+    pub semi_major_axis: S,
+    pub eccentricity: S,
+    pub argument_of_periapsis: S,
+    pub anomaly: Anomaly<S>,
+    pub retrograde: bool,
+}
+
+/// `angle` brought into (-π, π].
+fn wrap<const SCALE: i64>(angle: FixedPoint<SCALE>) -> Result<FixedPoint<SCALE>, SimError> {
+    let (sin, cos) = angle.checked_sin_cos()?;
+    sin.checked_atan2(cos)
+}
+
+impl<const SCALE: i64> OrbitalElements<FixedPoint<SCALE>> {
+    fn check(&self) -> Result<(), OrbitError> {
+        let e = self.eccentricity;
+        if self.semi_major_axis <= FixedPoint::ZERO || e < FixedPoint::ZERO || e >= FixedPoint::ONE
+        {
+            return Err(OrbitError::Unbound);
+        }
+        Ok(())
+    }
+
+    /// √(1 - e²), the ratio of the minor axis to the major one.
+    fn minor_ratio(&self) -> Result<FixedPoint<SCALE>, SimError> {
+        let e = self.eccentricity;
+        FixedPoint::ONE
+            .checked_sub(e.checked_mul(e)?)?
+            .checked_sqrt()
+    }
+
+    fn eccentric_anomaly(&self) -> Result<FixedPoint<SCALE>, SimError> {
+        let e = self.eccentricity;
+        match self.anomaly {
+            Anomaly::True(nu) => {
+                let (sin, cos) = nu.checked_sin_cos()?;
+                self.minor_ratio()?
+                    .checked_mul(sin)?
+                    .checked_atan2(e.checked_add(cos)?)
+            }
+            Anomaly::Mean(mean) => {
+                let mean = wrap(mean)?;
+                // starting from π past an eccentricity of 0.8 keeps Newton's method from
+                // overshooting near periapsis
+                let mut ecc = mean;
+                if e.raw() > SCALE / 5 * 4 {
+                    ecc = if mean < FixedPoint::ZERO {
+                        FixedPoint::PI.checked_neg()?
+                    } else {
+                        FixedPoint::PI
+                    };
+                }
+                for _ in 0..KEPLER_ITERATIONS {
+                    let (sin, cos) = ecc.checked_sin_cos()?;
+                    let error = ecc.checked_sub(e.checked_mul(sin)?)?.checked_sub(mean)?;
+                    let slope = FixedPoint::ONE.checked_sub(e.checked_mul(cos)?)?;
+                    let step = error.checked_div(slope)?;
+                    if step == FixedPoint::ZERO {
+                        break;
+                    }
+                    ecc = ecc.checked_sub(step)?;
+                }
+                Ok(ecc)
+            }
+        }
+    }
+
+    pub fn true_anomaly(&self) -> Result<FixedPoint<SCALE>, OrbitError> {
+        self.check()?;
+        if let Anomaly::True(nu) = self.anomaly {
+            return Ok(nu);
+        }
+        let (sin, cos) = self.eccentric_anomaly()?.checked_sin_cos()?;
+        Ok(self
+            .minor_ratio()?
+            .checked_mul(sin)?
+            .checked_atan2(cos.checked_sub(self.eccentricity)?)?)
+    }
+
+    pub fn mean_anomaly(&self) -> Result<FixedPoint<SCALE>, OrbitError> {
+        self.check()?;
+        if let Anomaly::Mean(mean) = self.anomaly {
+            return Ok(mean);
+        }
+        let ecc = self.eccentric_anomaly()?;
+        let sin = ecc.checked_sin()?;
+        Ok(ecc.checked_sub(self.eccentricity.checked_mul(sin)?)?)
+    }
+
+    /// A body of `mass` on this orbit about `primary`, under gravity of `grav_g`.
+    pub fn to_state(
+        self,
+        grav_g: i64,
+        primary: &MotionState<FixedPoint<SCALE>>,
+        id: u32,
+        mass: u64,
+    ) -> Result<MotionState<FixedPoint<SCALE>>, OrbitError> {
+        self.check()?;
+        let (a, e) = (self.semi_major_axis, self.eccentricity);
+        let mu = gravitational_parameter(grav_g, primary.get_mass(), mass)?;
+
+        // the position and velocity along the axes of the ellipse, periapsis on the first
+        let (sin, cos) = self.eccentric_anomaly()?.checked_sin_cos()?;
+        let minor_ratio = self.minor_ratio()?;
+        let r = a.checked_mul(FixedPoint::ONE.checked_sub(e.checked_mul(cos)?)?)?;
+        let speed = mu.checked_mul(a)?.checked_sqrt()?.checked_div(r)?;
+        let mut along = [
+            a.checked_mul(cos.checked_sub(e)?)?,
+            a.checked_mul(minor_ratio)?.checked_mul(sin)?,
+            speed.checked_mul(sin)?.checked_neg()?,
+            speed.checked_mul(minor_ratio)?.checked_mul(cos)?,
+        ];
+        if self.retrograde {
+            along[1] = along[1].checked_neg()?;
+            along[3] = along[3].checked_neg()?;
+        }
+
+        let (sin, cos) = self.argument_of_periapsis.checked_sin_cos()?;
+        let turn = |x: FixedPoint<SCALE>, y: FixedPoint<SCALE>| -> Result<_, SimError> {
+            Ok((
+                x.checked_mul(cos)?.checked_sub(y.checked_mul(sin)?)?,
+                x.checked_mul(sin)?.checked_add(y.checked_mul(cos)?)?,
+            ))
+        };
+        let (x, y) = turn(along[0], along[1])?;
+        let (vel_x, vel_y) = turn(along[2], along[3])?;
+
+        let position = primary.get_position();
+        let velocity = primary.get_velocity();
+        Ok(MotionState::from_raw(
+            id,
+            mass,
+            position[0].checked_add(x)?.raw(),
+            position[1].checked_add(y)?.raw(),
+            velocity[0].checked_add(vel_x)?.raw(),
+            velocity[1].checked_add(vel_y)?.raw(),
+        ))
+    }
+
+    /// The orbit of `state` about `primary` under gravity of `grav_g`, placed by its true
+    /// anomaly. Angles are in (-π, π].
+    pub fn from_state(
+        grav_g: i64,
+        primary: &MotionState<FixedPoint<SCALE>>,
+        state: &MotionState<FixedPoint<SCALE>>,
+    ) -> Result<Self, OrbitError> {
+        let mu = gravitational_parameter(grav_g, primary.get_mass(), state.get_mass())?;
+        if mu <= FixedPoint::ZERO {
+            return Err(OrbitError::Unbound);
+        }
+
+        let (position, velocity) = (state.get_position(), state.get_velocity());
+        let (primary_position, primary_velocity) = (primary.get_position(), primary.get_velocity());
+        let mut d = [FixedPoint::ZERO; 4];
+        for axis in 0..2 {
+            d[axis] = position[axis].checked_sub(primary_position[axis])?;
+            d[axis + 2] = velocity[axis].checked_sub(primary_velocity[axis])?;
+        }
+        let [x, mut y, vel_x, mut vel_y] = d;
+
+        // a retrograde orbit is read as its prograde mirror image
+        let angular_momentum = x.checked_mul(vel_y)?.checked_sub(y.checked_mul(vel_x)?)?;
+        let retrograde = angular_momentum < FixedPoint::ZERO;
+        if retrograde {
+            y = y.checked_neg()?;
+            vel_y = vel_y.checked_neg()?;
+        }
+
+        let r = FixedPoint::norm(&[x, y])?;
+        if r == FixedPoint::ZERO {
+            return Err(SimError::ZeroDistance.into());
+        }
+        let speed_squared = vel_x
+            .checked_mul(vel_x)?
+            .checked_add(vel_y.checked_mul(vel_y)?)?;
+
+        // a = μ r / (2 μ - v² r), from the energy
+        let denominator = mu
+            .checked_mul_int(2)?
+            .checked_sub(speed_squared.checked_mul(r)?)?;
+        if denominator <= FixedPoint::ZERO {
+            return Err(OrbitError::Unbound);
+        }
+        let semi_major_axis = mu.mul_div(r, denominator)?;
+
+        // the eccentricity vector, ((v² - μ / r) r - (r · v) v) / μ, points at periapsis
+        let excess = speed_squared.checked_sub(mu.checked_div(r)?)?;
+        let radial = x.checked_mul(vel_x)?.checked_add(y.checked_mul(vel_y)?)?;
+        let e_x = excess
+            .mul_div(x, mu)?
+            .checked_sub(radial.mul_div(vel_x, mu)?)?;
+        let e_y = excess
+            .mul_div(y, mu)?
+            .checked_sub(radial.mul_div(vel_y, mu)?)?;
+        let eccentricity = FixedPoint::norm(&[e_x, e_y])?;
+        if eccentricity >= FixedPoint::ONE {
+            return Err(OrbitError::Unbound);
+        }
+
+        let mut periapsis = e_y.checked_atan2(e_x)?;
+        let nu = wrap(y.checked_atan2(x)?.checked_sub(periapsis)?)?;
+        if retrograde {
+            periapsis = wrap(periapsis.checked_neg()?)?;
+        }
+        Ok(OrbitalElements {
+            semi_major_axis,
+            eccentricity,
+            argument_of_periapsis: periapsis,
+            anomaly: Anomaly::True(nu),
+            retrograde,
+        })
+    }
+}
+
+fn gravitational_parameter<const SCALE: i64>(
+    grav_g: i64,
+    mass: u64,
+    other_mass: u64,
+) -> Result<FixedPoint<SCALE>, SimError> {
+    let mass = mass as i128 + other_mass as i128;
+    FixedPoint::from_raw(grav_g).checked_mul(FixedPoint::from_wide(mass)?)
+}
+
+/// Builds a system body by body, each on an orbit about a body built before it, as a
+/// moon about its planet about its star. Ids are given in the order bodies are built,
+/// starting from 0 for the body at the centre.
+#[derive(Debug, Clone)]
+pub struct SystemBuilder<S = Fixed> {
+    grav_g: i64,
+    system: Vec<MotionState<S>>,
+}
+
+impl<const SCALE: i64> SystemBuilder<FixedPoint<SCALE>> {
+    /// A system of one body of `mass`, at rest at the origin, under gravity of `grav_g`.
+    pub fn new(grav_g: i64, mass: u64) -> Self {
+        Self {
+            grav_g,
+            system: alloc::vec![MotionState::from_raw(0, mass, 0, 0, 0, 0)],
+        }
+    }
+
+    /// Adds a body of `mass` on `elements` about the body with id `primary`, giving its id.
+    pub fn orbiting(
+        &mut self,
+        primary: u32,
+        mass: u64,
+        elements: &OrbitalElements<FixedPoint<SCALE>>,
+    ) -> Result<u32, OrbitError> {
+        let primary = self
+            .system
+            .get(primary as usize)
+            .ok_or(OrbitError::UnknownPrimary(primary))?;
+        let id = self.system.len() as u32;
+        let state = elements.to_state(self.grav_g, primary, id, mass)?;
+        self.system.push(state);
+        Ok(id)
+    }
+
+    pub fn build(self) -> Vec<MotionState<FixedPoint<SCALE>>> {
+        self.system
+    }
+}
@@ -0,0 +1,103 @@
+// Integer roots for the fixed-point backends. Every loop here runs a bounded number of
+// times, known from the width of its input, and the roots never divide. Both matter on
+// chain, where gas is paid per instruction and wide division is emulated in software.
+//
+// Gravity needs r⁻³ rather than r. Dividing by a rounded r three times rounds four
+// times, and the error in r itself is tripled. `mul_div_sqrt_cubed` instead works from
+// the squared distance, which is exact, and rounds twice, so `a * b / x^(3/2)` comes
+// out within one unit in the last place.
+
+/// The floor of the square root of `x`, found one bit of the root at a time. That takes
+/// one iteration per two bits of `x`, so at most 64, and at most 32 for any `x` that fits
+/// in a u64, which are done in u64 arithmetic.
+pub fn isqrt(x: u128) -> u128 {
+    if let Ok(x) = u64::try_from(x) {
+        return isqrt_u64(x) as u128;
+    }
+
+    // the highest power of four at or below x
+    let mut bit = 1u128 << ((127 - x.leading_zeros()) & !1);
+    let mut rest = x;
+    let mut root = 0;
+    while bit != 0 {
+        if rest >= root + bit {
+            rest -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+fn isqrt_u64(x: u64) -> u64 {
+    if x == 0 {
+        return 0;
+    }
+
+    let mut bit = 1u64 << ((63 - x.leading_zeros()) & !1);
+    let mut rest = x;
+    let mut root = 0;
+    while bit != 0 {
+        if rest >= root + bit {
+            rest -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+/// `a * b / c` rounded down, with the product held in 256 bits. `None` when the quotient
+/// does not fit in a u128 or `c` is zero.
+pub(crate) fn mul_div_u128(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+    if let Some(product) = a.checked_mul(b) {
+        return Some(product / c);
+    }
+
+    // schoolbook multiplication in 64 bit halves, giving the product as hi:lo
+    let mask = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & mask);
+    let (b_hi, b_lo) = (b >> 64, b & mask);
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let cross = (lo_lo >> 64) + (hi_lo & mask) + (lo_hi & mask);
+    let lo = (cross << 64) | (lo_lo & mask);
+    let hi = a_hi * b_hi + (hi_lo >> 64) + (lo_hi >> 64) + (cross >> 64);
+    if hi >= c {
+        return None;
+    }
+
+    // long division one bit at a time, the remainder staying below c
+    let mut remainder = hi;
+    let mut quotient = 0;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= c {
+            remainder = remainder.wrapping_sub(c);
+            quotient |= 1;
+        }
+    }
+    Some(quotient)
+}
+
+/// `n / √x` rounded down, exactly. `None` when `x` is zero or the quotient does not fit.
+pub fn div_sqrt(n: u128, x: u128) -> Option<u128> {
+    // the floor of a root is the root of the floor, so n² / x need only be rounded once
+    mul_div_u128(n, n, x).map(isqrt)
+}
+
+/// `a * b / x^(3/2)` rounded down, to within one. `None` when `x` is zero or the quotient
+/// does not fit.
+pub fn mul_div_sqrt_cubed(a: u128, b: u128, x: u128) -> Option<u128> {
+    div_sqrt(mul_div_u128(a, b, x)?, x)
+}
@@ -0,0 +1,484 @@
+use super::fixed::sqrt_heron;
+use super::math::{mul_div_sqrt_cubed, mul_div_u128};
+use super::{FixedPoint, SimError, PRECISION};
+
+use ::fixed::types::I64F64;
+use core::cmp::Ordering;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+// The integrators only ever handle positions and velocities through `Scalar`, so the
+// same code runs on any numeric backend. `FixedPoint` is the one tokens are minted with
+// and must stay bit-for-bit reproducible. The others are for running a reference
+// off-chain at a higher precision: `WideFixed` keeps ten more decimal places than
+// `Fixed` in an i128, `I64F64` is binary fixed-point and f64 is plain floating point.
+//
+// Masses, radii, softening and time steps stay integers scaled by the `SCALE` of the
+// backend, and are converted with `from_scaled` where they are used.
+
+/// A real number as the integrators see it. Every operation that can leave the range
+/// of the backend is checked and fails with `SimError::Overflow`, dividing by zero
+/// included.
+pub trait Scalar: Copy + PartialEq + PartialOrd + fmt::Debug {
+    /// The scale of the integers the backend is read from and written to.
+    const SCALE: i64;
+
+    const ZERO: Self;
+
+    /// The value of an integer scaled by `SCALE`, so `from_scaled(SCALE)` is one.
+    fn from_scaled(scaled: i128) -> Result<Self, SimError>;
+
+    /// The value scaled by `SCALE`, rounded towards zero and saturating.
+    fn to_scaled(self) -> i128;
+
+    fn checked_add(self, other: Self) -> Result<Self, SimError>;
+
+    fn checked_sub(self, other: Self) -> Result<Self, SimError>;
+
+    fn checked_mul(self, other: Self) -> Result<Self, SimError>;
+
+    fn checked_div(self, other: Self) -> Result<Self, SimError>;
+
+    /// `self * numerator / denominator`, with a single rounding where the backend allows.
+    fn mul_div(self, numerator: Self, denominator: Self) -> Result<Self, SimError>;
+
+    fn checked_mul_int(self, n: i64) -> Result<Self, SimError>;
+
+    fn checked_div_int(self, n: i64) -> Result<Self, SimError>;
+
+    fn saturating_sub(self, other: Self) -> Self;
+
+    /// The euclidean norm of `components`.
+    fn norm(components: &[Self]) -> Result<Self, SimError>;
+
+    /// `self * numerator / r³`, where `r` is the norm of `components`, as an inverse
+    /// square law needs it. By default the norm is taken and divided by three times.
+    fn mul_div_norm_cubed(self, numerator: Self, components: &[Self]) -> Result<Self, SimError> {
+        let r = Self::norm(components)?;
+        self.mul_div(numerator, r)?.checked_div(r)?.checked_div(r)
+    }
+
+    /// Compares the norm of `components` with `length`. A norm too large for the
+    /// backend is greater than any length.
+    fn cmp_norm(components: &[Self], length: Self) -> Ordering {
+        match Self::norm(components) {
+            Ok(norm) => norm.partial_cmp(&length).unwrap_or(Ordering::Greater),
+            Err(_) => Ordering::Greater,
+        }
+    }
+
+    /// The sum of `n * value` over `terms`, divided by `denominator`.
+    fn weighted_sum<I>(terms: I, denominator: i128) -> Result<Self, SimError>
+    where
+        I: IntoIterator<Item = (i128, Self)>;
+}
+
+impl<const SCALE: i64> Scalar for FixedPoint<SCALE> {
+    const SCALE: i64 = SCALE;
+
+    const ZERO: Self = FixedPoint::ZERO;
+
+    fn from_scaled(scaled: i128) -> Result<Self, SimError> {
+        FixedPoint::from_wide(scaled)
+    }
+
+    fn to_scaled(self) -> i128 {
+        self.raw() as i128
+    }
+
+    fn checked_add(self, other: Self) -> Result<Self, SimError> {
+        FixedPoint::checked_add(self, other)
+    }
+
+    fn checked_sub(self, other: Self) -> Result<Self, SimError> {
+        FixedPoint::checked_sub(self, other)
+    }
+
+    fn checked_mul(self, other: Self) -> Result<Self, SimError> {
+        FixedPoint::checked_mul(self, other)
+    }
+
+    fn checked_div(self, other: Self) -> Result<Self, SimError> {
+        FixedPoint::checked_div(self, other)
+    }
+
+    fn mul_div(self, numerator: Self, denominator: Self) -> Result<Self, SimError> {
+        FixedPoint::mul_div(self, numerator, denominator)
+    }
+
+    fn checked_mul_int(self, n: i64) -> Result<Self, SimError> {
+        FixedPoint::checked_mul_int(self, n)
+    }
+
+    fn checked_div_int(self, n: i64) -> Result<Self, SimError> {
+        FixedPoint::checked_div_int(self, n)
+    }
+
+    fn saturating_sub(self, other: Self) -> Self {
+        FixedPoint::saturating_sub(self, other)
+    }
+
+    /// The squares are summed exactly in i128 and rounded once by the square root.
+    fn norm(components: &[Self]) -> Result<Self, SimError> {
+        FixedPoint::from_wide(sqrt_heron(sum_of_squares(components)?)?)
+    }
+
+    /// From the exact sum of squares, without rounding the norm, and within a unit in the
+    /// last place, see `mul_div_sqrt_cubed`.
+    fn mul_div_norm_cubed(self, numerator: Self, components: &[Self]) -> Result<Self, SimError> {
+        let product = self.raw() as i128 * numerator.raw() as i128;
+        let scale = SCALE as u128;
+        let squared = sum_of_squares(components)? as u128;
+        let magnitude = mul_div_sqrt_cubed(product.unsigned_abs(), scale * scale, squared)
+            .ok_or(SimError::Overflow)?;
+        let magnitude = i64::try_from(magnitude).map_err(|_| SimError::Overflow)?;
+        if product < 0 {
+            Ok(FixedPoint::from_raw(-magnitude))
+        } else {
+            Ok(FixedPoint::from_raw(magnitude))
+        }
+    }
+
+    /// Exact, the squares are compared without taking a root.
+    fn cmp_norm(components: &[Self], length: Self) -> Ordering {
+        if length < FixedPoint::ZERO {
+            return Ordering::Greater;
+        }
+
+        match sum_of_squares(components) {
+            Ok(squared) => squared.cmp(&(length.raw() as i128 * length.raw() as i128)),
+            Err(_) => Ordering::Greater,
+        }
+    }
+
+    /// The sum is kept in i128, so only the result needs to fit.
+    fn weighted_sum<I>(terms: I, denominator: i128) -> Result<Self, SimError>
+    where
+        I: IntoIterator<Item = (i128, Self)>,
+    {
+        let mut sum: i128 = 0;
+        for (n, value) in terms {
+            sum = n
+                .checked_mul(value.raw() as i128)
+                .and_then(|term| sum.checked_add(term))
+                .ok_or(SimError::Overflow)?;
+        }
+        FixedPoint::from_wide(sum.checked_div(denominator).ok_or(SimError::Overflow)?)
+    }
+}
+
+fn sum_of_squares<const SCALE: i64>(components: &[FixedPoint<SCALE>]) -> Result<i128, SimError> {
+    let mut squared: i128 = 0;
+    for c in components {
+        squared = squared
+            .checked_add(c.raw() as i128 * c.raw() as i128)
+            .ok_or(SimError::Overflow)?;
+    }
+    Ok(squared)
+}
+
+pub const WIDE_PRECISION: i128 = 1000000000000000000;
+
+/// A fixed-point number held as an i128 scaled by `WIDE_PRECISION`, and read from and
+/// written to integers scaled by `PRECISION`.
+///
+/// Like `Fixed` it rounds towards zero, but products are formed in 256 bits so they
+/// only fail when the result itself does not fit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct WideFixed(i128);
+
+impl WideFixed {
+    pub const fn from_raw(raw: i128) -> Self {
+        WideFixed(raw)
+    }
+
+    pub const fn raw(self) -> i128 {
+        self.0
+    }
+}
+
+fn mul_div_wide(a: i128, b: i128, c: i128) -> Result<i128, SimError> {
+    let negative = (a < 0) ^ (b < 0) ^ (c < 0);
+    let magnitude = mul_div_u128(a.unsigned_abs(), b.unsigned_abs(), c.unsigned_abs())
+        .ok_or(SimError::Overflow)?;
+    if negative {
+        0i128
+            .checked_sub_unsigned(magnitude)
+            .ok_or(SimError::Overflow)
+    } else {
+        i128::try_from(magnitude).map_err(|_| SimError::Overflow)
+    }
+}
+
+/// `value` at a scale of `from` taken to a scale of `to`, rounding towards zero.
+pub fn rescale(value: i128, from: i64, to: i64) -> Result<i128, SimError> {
+    mul_div_wide(value, to as i128, from as i128)
+}
+
+/// The number of bits needed to hold `x`.
+fn bit_length(x: u128) -> u32 {
+    128 - x.leading_zeros()
+}
+
+impl Scalar for WideFixed {
+    const SCALE: i64 = PRECISION;
+
+    const ZERO: Self = WideFixed(0);
+
+    fn from_scaled(scaled: i128) -> Result<Self, SimError> {
+        scaled
+            .checked_mul(WIDE_PRECISION / PRECISION as i128)
+            .map(WideFixed)
+            .ok_or(SimError::Overflow)
+    }
+
+    fn to_scaled(self) -> i128 {
+        self.0 / (WIDE_PRECISION / PRECISION as i128)
+    }
+
+    fn checked_add(self, other: Self) -> Result<Self, SimError> {
+        self.0
+            .checked_add(other.0)
+            .map(WideFixed)
+            .ok_or(SimError::Overflow)
+    }
+
+    fn checked_sub(self, other: Self) -> Result<Self, SimError> {
+        self.0
+            .checked_sub(other.0)
+            .map(WideFixed)
+            .ok_or(SimError::Overflow)
+    }
+
+    fn checked_mul(self, other: Self) -> Result<Self, SimError> {
+        mul_div_wide(self.0, other.0, WIDE_PRECISION).map(WideFixed)
+    }
+
+    fn checked_div(self, other: Self) -> Result<Self, SimError> {
+        mul_div_wide(self.0, WIDE_PRECISION, other.0).map(WideFixed)
+    }
+
+    fn mul_div(self, numerator: Self, denominator: Self) -> Result<Self, SimError> {
+        mul_div_wide(self.0, numerator.0, denominator.0).map(WideFixed)
+    }
+
+    fn checked_mul_int(self, n: i64) -> Result<Self, SimError> {
+        self.0
+            .checked_mul(n as i128)
+            .map(WideFixed)
+            .ok_or(SimError::Overflow)
+    }
+
+    fn checked_div_int(self, n: i64) -> Result<Self, SimError> {
+        self.0
+            .checked_div(n as i128)
+            .map(WideFixed)
+            .ok_or(SimError::Overflow)
+    }
+
+    fn saturating_sub(self, other: Self) -> Self {
+        WideFixed(self.0.saturating_sub(other.0))
+    }
+
+    /// Heron's method on `squared * WIDE_PRECISION`, which does not fit in an i128 and
+    /// so is only ever divided through `mul_div_wide`.
+    fn norm(components: &[Self]) -> Result<Self, SimError> {
+        let mut squared = WideFixed::ZERO;
+        for c in components {
+            squared = squared.checked_add(c.checked_mul(*c)?)?;
+        }
+        if squared.0 == 0 {
+            return Ok(WideFixed::ZERO);
+        }
+
+        // a power of two at or above the root, from which the iterates only fall
+        let bits = bit_length(squared.0 as u128) + bit_length(WIDE_PRECISION as u128);
+        let mut x_old = i128::MAX;
+        let mut x_new = 1i128 << bits.div_ceil(2);
+        while x_new < x_old {
+            x_old = x_new;
+            x_new = (x_old + mul_div_wide(squared.0, WIDE_PRECISION, x_old)?) / 2;
+        }
+        Ok(WideFixed(x_old))
+    }
+
+    fn weighted_sum<I>(terms: I, denominator: i128) -> Result<Self, SimError>
+    where
+        I: IntoIterator<Item = (i128, Self)>,
+    {
+        let mut sum = WideFixed::ZERO;
+        for (n, value) in terms {
+            let term = value.0.checked_mul(n).ok_or(SimError::Overflow)?;
+            sum = sum.checked_add(WideFixed(term))?;
+        }
+        sum.0
+            .checked_div(denominator)
+            .map(WideFixed)
+            .ok_or(SimError::Overflow)
+    }
+}
+
+impl Scalar for I64F64 {
+    const SCALE: i64 = PRECISION;
+
+    const ZERO: Self = I64F64::ZERO;
+
+    fn from_scaled(scaled: i128) -> Result<Self, SimError> {
+        I64F64::checked_from_num(scaled)
+            .and_then(|value| value.checked_div_int(PRECISION as i128))
+            .ok_or(SimError::Overflow)
+    }
+
+    fn to_scaled(self) -> i128 {
+        self.saturating_mul_int(PRECISION as i128)
+            .round_to_zero()
+            .to_num()
+    }
+
+    fn checked_add(self, other: Self) -> Result<Self, SimError> {
+        I64F64::checked_add(self, other).ok_or(SimError::Overflow)
+    }
+
+    fn checked_sub(self, other: Self) -> Result<Self, SimError> {
+        I64F64::checked_sub(self, other).ok_or(SimError::Overflow)
+    }
+
+    fn checked_mul(self, other: Self) -> Result<Self, SimError> {
+        I64F64::checked_mul(self, other).ok_or(SimError::Overflow)
+    }
+
+    fn checked_div(self, other: Self) -> Result<Self, SimError> {
+        I64F64::checked_div(self, other).ok_or(SimError::Overflow)
+    }
+
+    fn mul_div(self, numerator: Self, denominator: Self) -> Result<Self, SimError> {
+        I64F64::checked_mul(self, numerator)
+            .and_then(|product| product.checked_div(denominator))
+            .ok_or(SimError::Overflow)
+    }
+
+    fn checked_mul_int(self, n: i64) -> Result<Self, SimError> {
+        I64F64::checked_mul_int(self, n as i128).ok_or(SimError::Overflow)
+    }
+
+    fn checked_div_int(self, n: i64) -> Result<Self, SimError> {
+        I64F64::checked_div_int(self, n as i128).ok_or(SimError::Overflow)
+    }
+
+    fn saturating_sub(self, other: Self) -> Self {
+        I64F64::saturating_sub(self, other)
+    }
+
+    fn norm(components: &[Self]) -> Result<Self, SimError> {
+        let mut squared = I64F64::ZERO;
+        for c in components {
+            squared = Scalar::checked_add(squared, Scalar::checked_mul(*c, *c)?)?;
+        }
+        squared.checked_sqrt().ok_or(SimError::NegativeSqrt)
+    }
+
+    fn weighted_sum<I>(terms: I, denominator: i128) -> Result<Self, SimError>
+    where
+        I: IntoIterator<Item = (i128, Self)>,
+    {
+        let mut sum = I64F64::ZERO;
+        for (n, value) in terms {
+            sum = value
+                .checked_mul_int(n)
+                .and_then(|term| sum.checked_add(term))
+                .ok_or(SimError::Overflow)?;
+        }
+        sum.checked_div_int(denominator).ok_or(SimError::Overflow)
+    }
+}
+
+fn finite(x: f64) -> Result<f64, SimError> {
+    if x.is_finite() {
+        Ok(x)
+    } else {
+        Err(SimError::Overflow)
+    }
+}
+
+/// Heron's method again, as `f64::sqrt` needs std. Halving the exponent gives a first
+/// guess within a factor of two, and after one step the iterates fall until they settle.
+fn sqrt_f64(x: f64) -> f64 {
+    if x == 0.0 {
+        return 0.0;
+    }
+
+    let guess = f64::from_bits((x.to_bits() >> 1) + (1023 << 51));
+    let mut x_old = f64::INFINITY;
+    let mut x_new = (guess + x / guess) / 2.0;
+    while x_new < x_old {
+        x_old = x_new;
+        x_new = (x_old + x / x_old) / 2.0;
+    }
+    x_old
+}
+
+impl Scalar for f64 {
+    const SCALE: i64 = PRECISION;
+
+    const ZERO: Self = 0.0;
+
+    fn from_scaled(scaled: i128) -> Result<Self, SimError> {
+        Ok(scaled as f64 / PRECISION as f64)
+    }
+
+    fn to_scaled(self) -> i128 {
+        (self * PRECISION as f64) as i128
+    }
+
+    fn checked_add(self, other: Self) -> Result<Self, SimError> {
+        finite(self + other)
+    }
+
+    fn checked_sub(self, other: Self) -> Result<Self, SimError> {
+        finite(self - other)
+    }
+
+    fn checked_mul(self, other: Self) -> Result<Self, SimError> {
+        finite(self * other)
+    }
+
+    fn checked_div(self, other: Self) -> Result<Self, SimError> {
+        finite(self / other)
+    }
+
+    fn mul_div(self, numerator: Self, denominator: Self) -> Result<Self, SimError> {
+        finite(self * numerator / denominator)
+    }
+
+    fn checked_mul_int(self, n: i64) -> Result<Self, SimError> {
+        finite(self * n as f64)
+    }
+
+    fn checked_div_int(self, n: i64) -> Result<Self, SimError> {
+        finite(self / n as f64)
+    }
+
+    fn saturating_sub(self, other: Self) -> Self {
+        (self - other).clamp(f64::MIN, f64::MAX)
+    }
+
+    fn norm(components: &[Self]) -> Result<Self, SimError> {
+        let mut squared = 0.0;
+        for c in components {
+            squared = finite(squared + c * c)?;
+        }
+        Ok(sqrt_f64(squared))
+    }
+
+    fn weighted_sum<I>(terms: I, denominator: i128) -> Result<Self, SimError>
+    where
+        I: IntoIterator<Item = (i128, Self)>,
+    {
+        let mut sum = 0.0;
+        for (n, value) in terms {
+            sum = finite(sum + n as f64 * value)?;
+        }
+        finite(sum / denominator as f64)
+    }
+}
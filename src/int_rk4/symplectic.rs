@@ -0,0 +1,129 @@
+use super::{Body, BodyKind, Fixed, Integrator, Scalar, SimError, PRECISION};
+
+use alloc::vec::Vec;
+
+// Symplectic integrators split each step into drifts, which move bodies along their
+// velocities, and kicks, which change velocities by the acceleration at the current
+// positions. Unlike the Runge-Kutta schemes they keep the energy error bounded, so
+// orbits stay closed over long runs. The acceleration is read from the velocity
+// components of the rate function. Anchors are never drifted, and their rates leave
+// their velocities as they are.
+
+fn drift<B: Body>(system: &[B], time_period_sec: i64) -> Result<Vec<B>, SimError> {
+    let time_period_sec = B::Scalar::from_scaled(time_period_sec as i128)?;
+    system
+        .iter()
+        .map(|state| {
+            if state.get_kind() == BodyKind::Anchor {
+                return Ok(state.clone());
+            }
+            let mut position = state.get_position();
+            let velocity = state.get_velocity();
+            for axis in 0..B::DIMENSIONS {
+                position[axis] =
+                    position[axis].checked_add(velocity[axis].checked_mul(time_period_sec)?)?;
+            }
+            Ok(state.with_motion(position, velocity))
+        })
+        .collect()
+}
+
+fn kick<B, F>(system: &[B], time_period_sec: i64, func: &F) -> Result<Vec<B>, SimError>
+where
+    B: Body,
+    F: Fn(&[B]) -> Result<Vec<B>, SimError>,
+{
+    let time_period_sec = B::Scalar::from_scaled(time_period_sec as i128)?;
+    let rates = func(system)?;
+    system
+        .iter()
+        .zip(&rates)
+        .map(|(state, rate)| {
+            let mut velocity = state.get_velocity();
+            let acceleration = rate.get_velocity();
+            for axis in 0..B::DIMENSIONS {
+                velocity[axis] =
+                    velocity[axis].checked_add(acceleration[axis].checked_mul(time_period_sec)?)?;
+            }
+            Ok(state.with_motion(state.get_position(), velocity))
+        })
+        .collect()
+}
+
+/// Splits `time_period_sec` by `coefficients` (scaled by `PRECISION`). The last part
+/// takes whatever the others leave, so the parts always sum to the whole step.
+fn split(time_period_sec: i64, coefficients: &[i64]) -> Result<Vec<i64>, SimError> {
+    let time_period_sec = Fixed::from_raw(time_period_sec);
+    let mut parts = coefficients
+        .iter()
+        .map(|c| Fixed::from_raw(*c).checked_mul(time_period_sec))
+        .collect::<Result<Vec<Fixed>, SimError>>()?;
+    if parts.pop().is_some() {
+        let mut rest = time_period_sec;
+        for part in &parts {
+            rest = rest.checked_sub(*part)?;
+        }
+        parts.push(rest);
+    }
+    Ok(parts.iter().map(|part| part.raw()).collect())
+}
+
+/// Drift-kick-drift leapfrog, second order with one force evaluation per step.
+pub struct Leapfrog;
+
+impl Integrator for Leapfrog {
+    fn step<B, F>(&self, time_period_sec: i64, system: &[B], func: F) -> Result<Vec<B>, SimError>
+    where
+        B: Body,
+        F: Fn(&[B]) -> Result<Vec<B>, SimError>,
+    {
+        let half = split(time_period_sec, &[PRECISION / 2, PRECISION / 2])?;
+
+        let next_system = drift(system, half[0])?;
+        let next_system = kick(&next_system, time_period_sec, &func)?;
+        drift(&next_system, half[1])
+    }
+}
+
+/// Kick-drift-kick velocity Verlet, second order with two force evaluations per step.
+pub struct VelocityVerlet;
+
+impl Integrator for VelocityVerlet {
+    fn step<B, F>(&self, time_period_sec: i64, system: &[B], func: F) -> Result<Vec<B>, SimError>
+    where
+        B: Body,
+        F: Fn(&[B]) -> Result<Vec<B>, SimError>,
+    {
+        let half = split(time_period_sec, &[PRECISION / 2, PRECISION / 2])?;
+
+        let next_system = kick(system, half[0], &func)?;
+        let next_system = drift(&next_system, time_period_sec)?;
+        kick(&next_system, half[1], &func)
+    }
+}
+
+// Yoshida's fourth order coefficients, w1 = 1 / (2 - 2^(1/3)) and w0 = 1 - 2 * w1,
+// scaled by PRECISION. Each set sums to PRECISION.
+const YOSHIDA_DRIFTS: [i64; 4] = [67560360, -17560360, -17560360, 67560360];
+const YOSHIDA_KICKS: [i64; 3] = [135120719, -170241438, 135120719];
+
+/// Yoshida's fourth order composition of leapfrog, three force evaluations per step.
+pub struct Yoshida4;
+
+impl Integrator for Yoshida4 {
+    fn step<B, F>(&self, time_period_sec: i64, system: &[B], func: F) -> Result<Vec<B>, SimError>
+    where
+        B: Body,
+        F: Fn(&[B]) -> Result<Vec<B>, SimError>,
+    {
+        let drifts = split(time_period_sec, &YOSHIDA_DRIFTS)?;
+        let kicks = split(time_period_sec, &YOSHIDA_KICKS)?;
+
+        let mut next_system = drift(system, drifts[0])?;
+        for i in 0..kicks.len() {
+            next_system = kick(&next_system, kicks[i], &func)?;
+            next_system = drift(&next_system, drifts[i + 1])?;
+        }
+        Ok(next_system)
+    }
+}
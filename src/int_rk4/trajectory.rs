@@ -0,0 +1,166 @@
+use super::{
+    tick_force_into, Body, Checkpoint, ForceLaw, ForceModel, Gravity, PhysicsParams, SimError,
+};
+
+use alloc::vec::Vec;
+use core::mem;
+
+// A trajectory steps a system tick by tick and hands out the states along the way, so
+// callers need not loop over `tick` themselves. The system stays with the trajectory
+// and each sample only borrows it, so nothing is cloned for the caller. Each step is
+// written into a second system of the same size, which then swaps places with the first,
+// so the two are reused for the whole run. That borrow is also why it is not an
+// `Iterator`: like a lending iterator, a sample must be dropped before the next one is
+// taken.
+
+/// The states of a system over `ticks` steps of `params` under `force`. Samples are
+/// taken at tick 0 and every `stride` ticks after, and always at the last tick, which
+/// is `ticks` or the first tick at which `stop` holds, whichever comes first.
+pub struct Trajectory<B, F = fn(u32, &[B]) -> bool, M = Gravity> {
+    params: PhysicsParams,
+    force: M,
+    system: Vec<B>,
+    /// The buffer the next step is written into.
+    next_system: Vec<B>,
+    tick: u32,
+    ticks: u32,
+    stride: u32,
+    /// The next tick due a sample by stride.
+    next_stride: u32,
+    stop: F,
+    started: bool,
+    done: bool,
+}
+
+impl<B: Body> Trajectory<B> {
+    pub fn new(params: PhysicsParams, ticks: u32, system: Vec<B>) -> Self {
+        Trajectory {
+            params,
+            force: Gravity,
+            next_system: system.clone(),
+            system,
+            tick: 0,
+            ticks,
+            stride: 1,
+            next_stride: 1,
+            stop: |_, _| false,
+            started: false,
+            done: false,
+        }
+    }
+}
+
+impl<B, F, M> Trajectory<B, F, M>
+where
+    B: Body,
+    F: FnMut(u32, &[B]) -> bool,
+    M: ForceModel,
+{
+    /// The same trajectory sampled every `stride` ticks, a stride of 0 being taken as 1.
+    pub fn with_stride(self, stride: u32) -> Self {
+        let stride = stride.max(1);
+        Trajectory {
+            stride,
+            next_stride: self.tick.saturating_add(stride),
+            ..self
+        }
+    }
+
+    /// The same trajectory ending at the first tick whose system satisfies `stop`.
+    pub fn with_stop<G>(self, stop: G) -> Trajectory<B, G, M>
+    where
+        G: FnMut(u32, &[B]) -> bool,
+    {
+        Trajectory {
+            params: self.params,
+            force: self.force,
+            system: self.system,
+            next_system: self.next_system,
+            tick: self.tick,
+            ticks: self.ticks,
+            stride: self.stride,
+            next_stride: self.next_stride,
+            stop,
+            started: self.started,
+            done: self.done,
+        }
+    }
+
+    /// The same trajectory under `force` rather than gravity.
+    pub fn with_force<N: ForceModel>(self, force: N) -> Trajectory<B, F, N> {
+        Trajectory {
+            params: self.params,
+            force,
+            system: self.system,
+            next_system: self.next_system,
+            tick: self.tick,
+            ticks: self.ticks,
+            stride: self.stride,
+            next_stride: self.next_stride,
+            stop: self.stop,
+            started: self.started,
+            done: self.done,
+        }
+    }
+
+    /// Steps to the next sample and returns its tick and system, `None` once the last
+    /// tick has been handed out or a step has failed.
+    pub fn next_sample(&mut self) -> Option<Result<(u32, &[B]), SimError>> {
+        if self.done {
+            return None;
+        }
+
+        if !self.started {
+            self.started = true;
+            self.done = self.ticks == 0 || (self.stop)(0, &self.system);
+            return Some(Ok((0, &self.system)));
+        }
+
+        loop {
+            let step = tick_force_into(
+                &self.params,
+                &self.force,
+                &self.system,
+                &mut self.next_system,
+            );
+            if let Err(err) = step {
+                self.done = true;
+                return Some(Err(err));
+            }
+            mem::swap(&mut self.system, &mut self.next_system);
+            self.tick += 1;
+
+            let strided = self.tick == self.next_stride;
+            if strided {
+                self.next_stride = self.next_stride.saturating_add(self.stride);
+            }
+
+            self.done = self.tick == self.ticks || (self.stop)(self.tick, &self.system);
+            if self.done || strided {
+                return Some(Ok((self.tick, &self.system)));
+            }
+        }
+    }
+
+    /// The system as of the last sample.
+    pub fn into_system(self) -> Vec<B> {
+        self.system
+    }
+}
+
+impl<B, F, M> Trajectory<B, F, M>
+where
+    B: Body,
+    M: Clone + Into<ForceLaw>,
+{
+    /// A checkpoint of the run as of the last sample, to be resumed past this trajectory
+    /// under the same force law.
+    pub fn checkpoint(&self) -> Checkpoint<B> {
+        Checkpoint {
+            params: self.params,
+            force: self.force.clone().into(),
+            tick: self.tick,
+            system: self.system.clone(),
+        }
+    }
+}
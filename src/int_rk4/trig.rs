@@ -0,0 +1,142 @@
+use super::fixed::{FixedPoint, SimError};
+
+// Sines, cosines and arctangents by CORDIC, which turns a vector through a fixed
+// sequence of angles atan(2^-i), each turn taking only shifts and additions. Every
+// call runs the same 61 turns, whatever its input, and gives the same bits on any
+// machine, unlike a floating-point libm. Angles are in radians.
+//
+// The turns are done in i128 with 60 fractional bits, well beyond any scale a token
+// is minted at, so the only rounding that shows is that of the final rescaling. A
+// vector turned this way grows by a constant factor, so sines and cosines start from
+// its inverse, K, rather than from one.
+
+const FRACTION_BITS: u32 = 60;
+const TURNS: u32 = 61;
+
+/// π with 60 fractional bits.
+const PI: i128 = 3622009729038561421;
+const HALF_PI: i128 = PI / 2;
+const TAU: i128 = PI * 2;
+
+/// The product of cos(atan(2^-i)) over all the turns, with 60 fractional bits.
+const K: i128 = 700114967507363238;
+
+/// atan(2^-i) with 60 fractional bits, for the turns where it differs from 2^-i.
+const ATAN: [i128; 20] = [
+    905502432259640355,
+    534549298976576474,
+    282441168888798124,
+    143371547418228444,
+    71963988336308046,
+    36017075762092179,
+    18012932708689205,
+    9007016009513623,
+    4503576721087964,
+    2251796950380271,
+    1125899548928887,
+    562949908682076,
+    281474971118251,
+    140737487656277,
+    70368744090283,
+    35184372077909,
+    17592186043051,
+    8796093022037,
+    4398046511083,
+    2199023255549,
+];
+
+fn atan_pow2(i: u32) -> i128 {
+    match ATAN.get(i as usize) {
+        Some(angle) => *angle,
+        None => 1 << (FRACTION_BITS - i),
+    }
+}
+
+/// `x` with 60 fractional bits at a scale of `SCALE`, rounded to nearest.
+fn rescale<const SCALE: i64>(x: i128) -> Result<FixedPoint<SCALE>, SimError> {
+    let half = 1 << (FRACTION_BITS - 1);
+    FixedPoint::from_wide((x * SCALE as i128 + half) >> FRACTION_BITS)
+}
+
+/// The cosine and sine of `angle`, with 60 fractional bits.
+fn cos_sin(angle: i128) -> (i128, i128) {
+    // turns only reach about ±1.74, so angles beyond ±π/2 are turned by π first
+    let mut z = angle.rem_euclid(TAU);
+    if z > PI {
+        z -= TAU;
+    }
+    let flip = z.abs() > HALF_PI;
+    if flip {
+        z -= PI * z.signum();
+    }
+
+    let (mut x, mut y) = (K, 0);
+    for i in 0..TURNS {
+        let (dx, dy) = (y >> i, x >> i);
+        if z >= 0 {
+            (x, y) = (x - dx, y + dy);
+            z -= atan_pow2(i);
+        } else {
+            (x, y) = (x + dx, y - dy);
+            z += atan_pow2(i);
+        }
+    }
+    if flip {
+        (-x, -y)
+    } else {
+        (x, y)
+    }
+}
+
+impl<const SCALE: i64> FixedPoint<SCALE> {
+    /// π, rounded to nearest.
+    pub const PI: Self = FixedPoint::from_raw(
+        ((PI * SCALE as i128 + (1 << (FRACTION_BITS - 1))) >> FRACTION_BITS) as i64,
+    );
+
+    /// The sine and cosine of `self`, rounded to nearest.
+    pub fn checked_sin_cos(self) -> Result<(Self, Self), SimError> {
+        let angle = ((self.raw() as i128) << FRACTION_BITS) / SCALE as i128;
+        let (cos, sin) = cos_sin(angle);
+        Ok((rescale(sin)?, rescale(cos)?))
+    }
+
+    pub fn checked_sin(self) -> Result<Self, SimError> {
+        Ok(self.checked_sin_cos()?.0)
+    }
+
+    pub fn checked_cos(self) -> Result<Self, SimError> {
+        Ok(self.checked_sin_cos()?.1)
+    }
+
+    /// The angle of the point (`x`, `self`) from the x axis, in (-π, π] and rounded to
+    /// nearest. The angle of the origin is 0.
+    pub fn checked_atan2(self, x: Self) -> Result<Self, SimError> {
+        let (mut x, mut y) = (x.raw() as i128, self.raw() as i128);
+        if x == 0 && y == 0 {
+            return Ok(Self::ZERO);
+        }
+
+        // the point is scaled up to 100 bits, for the turns to lose nothing, and the left
+        // half plane is turned by π into the right one that the turns reach
+        let shift = 100 - (128 - x.abs().max(y.abs()).leading_zeros());
+        (x, y) = (x << shift, y << shift);
+        let mut z = 0;
+        if x < 0 {
+            z = if y >= 0 { PI } else { -PI };
+            (x, y) = (-x, -y);
+        }
+
+        for i in 0..TURNS {
+            let (dx, dy) = (y >> i, x >> i);
+            if y < 0 {
+                (x, y) = (x - dx, y + dy);
+                z -= atan_pow2(i);
+            } else {
+                (x, y) = (x + dx, y - dy);
+                z += atan_pow2(i);
+            }
+        }
+        rescale(z)
+    }
+}
@@ -1,5 +1,8 @@
 // #![cfg_attr(not(feature = "export-abi"), no_main, no_std)]
 extern crate alloc;
 
+pub mod diagnostics;
 pub mod int_rk4;
+pub mod mint;
+pub mod presets;
 pub mod wasm_entry;
\ No newline at end of file
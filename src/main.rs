@@ -5,21 +5,23 @@ extern crate alloc;
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 mod erc721;
-mod int_rk4;
-use crate::int_rk4::{tick_many, MotionState};
 
 use crate::erc721::{Erc721, Erc721Params};
 use alloc::vec::Vec;
-use alloy_primitives::keccak256;
 use erc721::Erc721Error;
-use int_rk4::PRECISION;
+use rk_fall::diagnostics::{diagnostics, Diagnostics};
+use rk_fall::int_rk4::{BodyKind, PhysicsParams, Scheme, SimError, DECIMALS, PRECISION};
+use rk_fall::mint::{
+    check_lengths, initial_system, kinds_of, minted, minted_3d, minted_merging, scale_of, Bodies,
+    Bodies3D, MintError, MintOptions, Minted,
+};
+use stylus_sdk::evm;
 use stylus_sdk::stylus_proc::{entrypoint, external, sol_storage};
 use stylus_sdk::{
     alloy_primitives::U256,
-    alloy_sol_types::{sol, SolError, SolType},
+    alloy_sol_types::{sol, SolError},
     msg,
 };
-use stylus_sdk::{console, evm};
 
 struct RkFallParams;
 
@@ -38,16 +40,61 @@ sol_storage! {
 
 sol! {
     event RkFallMint(uint256 indexed tokenId,uint32 ticks,uint64[] mass,int64[] init_x,int64[] init_y,int64[] init_vel_x,int64[] init_vel_y,int64[] final_x,int64[] final_y,int64[] final_vel_x,int64[] final_vel_y);
+    event RkFallScheme(uint256 indexed tokenId, uint8 scheme);
+    event RkFallSoftening(uint256 indexed tokenId, int64 softening);
+    event RkFallScale(uint256 indexed tokenId, uint8 decimals);
+    event RkFallPhysics(uint256 indexed tokenId, int64 gravG, int64 timePeriodSec);
+    event RkFallForce(uint256 indexed tokenId, uint8[] force);
+    event RkFallKinds(uint256 indexed tokenId, uint8[] kind);
+    event RkFallRadius(uint256 indexed tokenId, uint64[] radius);
+    event RkFallEvent(uint256 indexed tokenId,uint32 tick,uint8 kind,uint32 id,uint32 otherId);
+    event RkFallCentred(uint256 indexed tokenId);
+    event RkFallCollision(uint256 indexed tokenId,uint32 tick,uint32 id,uint32 otherId,uint64 mass,uint64 radius,int64 x,int64 y,int64 vel_x,int64 vel_y);
+    event RkFallMint3D(uint256 indexed tokenId,uint8 scheme,uint32 ticks,uint64[] mass,int64[] init_x,int64[] init_y,int64[] init_z,int64[] init_vel_x,int64[] init_vel_y,int64[] init_vel_z,int64[] final_x,int64[] final_y,int64[] final_z,int64[] final_vel_x,int64[] final_vel_y,int64[] final_vel_z);
     error AlreadyMinted(uint256 tokenId, address owner);
+    error UnknownScheme(uint8 scheme);
+    error MismatchedLengths(uint256 bodies, uint256 length);
+    error Overflow();
+    error ZeroDistance();
+    error NegativeSqrt();
+    error UnsupportedScale(uint8 decimals);
+    error UnknownForce();
+    error UnknownKind(uint8 kind);
 }
 
 pub enum RkFallError {
     AlreadyMinted(AlreadyMinted),
+    UnknownScheme(UnknownScheme),
+    MismatchedLengths(MismatchedLengths),
+    Overflow(Overflow),
+    ZeroDistance(ZeroDistance),
+    NegativeSqrt(NegativeSqrt),
+    UnsupportedScale(UnsupportedScale),
+    UnknownForce(UnknownForce),
+    UnknownKind(UnknownKind),
 }
 impl From<RkFallError> for Vec<u8> {
     fn from(err: RkFallError) -> Vec<u8> {
         match err {
             RkFallError::AlreadyMinted(e) => e.encode(),
+            RkFallError::UnknownScheme(e) => e.encode(),
+            RkFallError::MismatchedLengths(e) => e.encode(),
+            RkFallError::Overflow(e) => e.encode(),
+            RkFallError::ZeroDistance(e) => e.encode(),
+            RkFallError::NegativeSqrt(e) => e.encode(),
+            RkFallError::UnsupportedScale(e) => e.encode(),
+            RkFallError::UnknownForce(e) => e.encode(),
+            RkFallError::UnknownKind(e) => e.encode(),
+        }
+    }
+}
+
+impl From<SimError> for RkFallError {
+    fn from(err: SimError) -> Self {
+        match err {
+            SimError::Overflow => RkFallError::Overflow(Overflow {}),
+            SimError::ZeroDistance => RkFallError::ZeroDistance(ZeroDistance {}),
+            SimError::NegativeSqrt => RkFallError::NegativeSqrt(NegativeSqrt {}),
         }
     }
 }
@@ -67,6 +114,12 @@ impl From<RkFallError> for CombinedError {
         CombinedError::DynDanceError(err)
     }
 }
+
+impl From<SimError> for CombinedError {
+    fn from(err: SimError) -> Self {
+        CombinedError::DynDanceError(err.into())
+    }
+}
 impl From<CombinedError> for Vec<u8> {
     fn from(err: CombinedError) -> Vec<u8> {
         match err {
@@ -78,6 +131,44 @@ impl From<CombinedError> for Vec<u8> {
 
 type CombinedResult<T> = Result<T, CombinedError>;
 
+impl From<MintError> for RkFallError {
+    fn from(err: MintError) -> Self {
+        match err {
+            MintError::UnknownScheme(scheme) => {
+                RkFallError::UnknownScheme(UnknownScheme { scheme })
+            }
+            MintError::MismatchedLengths { bodies, length } => {
+                RkFallError::MismatchedLengths(MismatchedLengths {
+                    bodies: U256::from(bodies),
+                    length: U256::from(length),
+                })
+            }
+            MintError::UnsupportedScale(decimals) => {
+                RkFallError::UnsupportedScale(UnsupportedScale { decimals })
+            }
+            MintError::UnknownForce => RkFallError::UnknownForce(UnknownForce {}),
+            MintError::UnknownKind(kind) => RkFallError::UnknownKind(UnknownKind { kind }),
+            MintError::Sim(err) => err.into(),
+        }
+    }
+}
+
+impl From<MintError> for CombinedError {
+    fn from(err: MintError) -> Self {
+        CombinedError::DynDanceError(err.into())
+    }
+}
+
+/// The `Diagnostics` of the planar system of `motion` at a scale of `SCALE`.
+fn diagnostics_at<const SCALE: i64>(
+    params: &PhysicsParams,
+    kinds: &[BodyKind],
+    mass: &[u64],
+    motion: [&[i64]; 4],
+) -> Result<Diagnostics, SimError> {
+    diagnostics(params, &initial_system::<SCALE>(kinds, mass, motion))
+}
+
 #[external]
 #[inherit(Erc721<RkFallParams>)]
 impl RkFall {
@@ -91,50 +182,255 @@ impl RkFall {
         vel_y: Vec<i64>,
         ticks: u32,
     ) -> CombinedResult<U256> {
-        // initial conditions
-        let mut initial_system = Vec::new();
-        for i in 0..mass.len() {
-            let state = MotionState::new(mass[i], x[i], y[i], vel_x[i], vel_y[i]);
-            initial_system.push(state);
+        let defaults = PhysicsParams::default();
+        let options = (
+            DECIMALS,
+            Scheme::PerBodyRk4.id(),
+            defaults.grav_g,
+            defaults.time_period_sec,
+            0,
+            Vec::new(),
+            Vec::new(),
+            0,
+            false,
+            false,
+            false,
+        );
+        self.mint_with_options(options, (mass, x, y, vel_x, vel_y), ticks)
+    }
+
+    /// Mints with the given `options`, those of `mint` being the defaults:
+    ///
+    /// * `decimals` is the precision, one of 6, 8, 10 or 12 decimal places rather than the
+    ///   default 8. All values are then raw at that scale, the step included
+    /// * `scheme` and `softening` are the integration scheme and the gravitational
+    ///   softening length, see `Scheme` and `grav_rate_func_softened`
+    /// * `grav_g` and `time_period_sec` are the gravitational constant and the step, see
+    ///   `PhysicsParams`
+    /// * `force` holds an encoded `ForceLaw` to move under rather than gravity, see
+    ///   `ForceLaw::decode`. No bytes at all are gravity
+    /// * `kind` holds the id of the `BodyKind` of each body, making anchors and tracers
+    ///   among them. No ids at all are free bodies only
+    /// * `close_approach`, `escapes` and `apsides` log the events of the run as
+    ///   `RkFallEvent`s: pairs of bodies coming closer than `close_approach`, unless it is
+    ///   0, and bodies becoming unbound and passing periapsis or apoapsis, see
+    ///   `EventDetector`. Events do not change how the bodies move, so they are left out
+    ///   of the token id
+    /// * `centre` moves the system into the frame of its centre of mass first, see
+    ///   `centre_of_mass_frame`. The initial arrays of `RkFallMint` then hold the centred
+    ///   system the bodies start from, while the token id is taken from the arrays as
+    ///   given and the flag, and the token is marked by an `RkFallCentred` event
+    pub fn mint_with_options(
+        &mut self,
+        options: MintOptions,
+        bodies: Bodies,
+        ticks: u32,
+    ) -> CombinedResult<U256> {
+        let Minted {
+            token_id,
+            mass,
+            init,
+            last,
+            events,
+            force,
+            kind,
+            default_physics,
+        } = minted(&options, bodies, ticks)?;
+        let (decimals, scheme, grav_g, time_period_sec, softening, _, _, _, _, _, centre) = options;
+        self.erc721._mint(msg::sender(), token_id)?;
+
+        let [init_x, init_y, init_vel_x, init_vel_y] = init;
+        let [final_x, final_y, final_vel_x, final_vel_y] = last;
+        evm::log(RkFallMint {
+            tokenId: token_id,
+            ticks,
+            mass,
+            init_x,
+            init_y,
+            init_vel_x,
+            init_vel_y,
+            final_x,
+            final_y,
+            final_vel_x,
+            final_vel_y,
+        });
+
+        // tokens without a scheme log were minted with the per-body scheme
+        if scheme != Scheme::PerBodyRk4.id() {
+            evm::log(RkFallScheme {
+                tokenId: token_id,
+                scheme,
+            });
         }
 
-        let time_period_sec = (0.001 * PRECISION as f64) as i64;
+        // tokens without a softening log were minted with plain gravity
+        if softening != 0 {
+            evm::log(RkFallSoftening {
+                tokenId: token_id,
+                softening,
+            });
+        }
 
-        // compute the result
-        let final_system = tick_many(ticks, time_period_sec, &initial_system);
+        // tokens without a scale log were minted with 8 decimal places
+        if decimals != DECIMALS {
+            evm::log(RkFallScale {
+                tokenId: token_id,
+                decimals,
+            });
+        }
 
-        // token id is taken from initial conditions
-        let data = <sol! { (uint64[], int64[], int64[], int64[], int64[], uint32)}>::encode(&(
-            mass.clone(),
-            x.clone(),
-            y.clone(),
-            vel_x.clone(),
-            vel_y.clone(),
-            ticks,
-        ));
-        let token_id = keccak256(data).into();
+        // tokens without a physics log were minted with a gravity of 1 and a step of
+        // 0.001 at their scale
+        if !default_physics {
+            evm::log(RkFallPhysics {
+                tokenId: token_id,
+                gravG: grav_g,
+                timePeriodSec: time_period_sec,
+            });
+        }
+
+        // tokens without a force log were minted with gravity
+        if !force.is_empty() {
+            evm::log(RkFallForce {
+                tokenId: token_id,
+                force,
+            });
+        }
+
+        // tokens without a kinds log were minted with free bodies only
+        if !kind.is_empty() {
+            evm::log(RkFallKinds {
+                tokenId: token_id,
+                kind,
+            });
+        }
+
+        // tokens without a centred log started from the system as given
+        if centre {
+            evm::log(RkFallCentred { tokenId: token_id });
+        }
+
+        for event in events {
+            evm::log(RkFallEvent {
+                tokenId: token_id,
+                tick: event.tick,
+                kind: event.kind.id(),
+                id: event.id,
+                otherId: event.other_id,
+            });
+        }
+
+        Ok(token_id)
+    }
+
+    /// Mints with bodies of the given radii that merge when they touch. The final
+    /// arrays of `RkFallMint` then hold only the bodies left at the end, in the order
+    /// of their ids, and each merger is logged as an `RkFallCollision`. `bodies` are as
+    /// in `mint_with_options`, with a radius for each in `radius`. Only the scheme
+    /// and softening can be chosen: the bodies are free and move under gravity at the
+    /// defaults of `mint`, 8 decimal places with a gravity of 1 and a step of 0.001, and
+    /// no other events are logged.
+    pub fn mint_merging(
+        &mut self,
+        scheme: u8,
+        softening: i64,
+        bodies: Bodies,
+        radius: Vec<u64>,
+        ticks: u32,
+    ) -> CombinedResult<U256> {
+        let (token_id, run) = minted_merging(scheme, softening, &bodies, &radius, ticks)?;
+        let (mass, x, y, vel_x, vel_y) = bodies;
+        let final_system = run.system;
         self.erc721._mint(msg::sender(), token_id)?;
 
         evm::log(RkFallMint {
             tokenId: token_id,
-            ticks: ticks,
-            mass: mass,
-            init_x: initial_system
+            ticks,
+            mass,
+            init_x: x,
+            init_y: y,
+            init_vel_x: vel_x,
+            init_vel_y: vel_y,
+            final_x: final_system
                 .iter()
                 .map(|state| state.get_x())
                 .collect::<Vec<i64>>(),
-            init_y: initial_system
+            final_y: final_system
                 .iter()
                 .map(|state| state.get_y())
                 .collect::<Vec<i64>>(),
-            init_vel_x: initial_system
+            final_vel_x: final_system
                 .iter()
                 .map(|state| state.get_vel_x())
                 .collect::<Vec<i64>>(),
-            init_vel_y: initial_system
+            final_vel_y: final_system
                 .iter()
                 .map(|state| state.get_vel_y())
                 .collect::<Vec<i64>>(),
+        });
+
+        if scheme != Scheme::PerBodyRk4.id() {
+            evm::log(RkFallScheme {
+                tokenId: token_id,
+                scheme,
+            });
+        }
+        if softening != 0 {
+            evm::log(RkFallSoftening {
+                tokenId: token_id,
+                softening,
+            });
+        }
+
+        // tokens with a radius log were minted with merging bodies
+        evm::log(RkFallRadius {
+            tokenId: token_id,
+            radius,
+        });
+        for collision in run.collisions {
+            evm::log(RkFallCollision {
+                tokenId: token_id,
+                tick: collision.tick,
+                id: collision.id,
+                otherId: collision.other_id,
+                mass: collision.merged.get_mass(),
+                radius: collision.merged.get_radius(),
+                x: collision.merged.get_x(),
+                y: collision.merged.get_y(),
+                vel_x: collision.merged.get_vel_x(),
+                vel_y: collision.merged.get_vel_y(),
+            });
+        }
+
+        Ok(token_id)
+    }
+
+    /// Mints bodies free to leave the plane, given as the mass, x, y, z, vel_x, vel_y and
+    /// vel_z arrays and logged as an `RkFallMint3D`. Only the scheme
+    /// and softening can be chosen: the bodies are free and move under gravity at the
+    /// defaults of `mint`, 8 decimal places with a gravity of 1 and a step of 0.001.
+    pub fn mint_3d(
+        &mut self,
+        scheme: u8,
+        softening: i64,
+        bodies: Bodies3D,
+        ticks: u32,
+    ) -> CombinedResult<U256> {
+        let (token_id, final_system) = minted_3d(scheme, softening, &bodies, ticks)?;
+        let (mass, x, y, z, vel_x, vel_y, vel_z) = bodies;
+        self.erc721._mint(msg::sender(), token_id)?;
+
+        evm::log(RkFallMint3D {
+            tokenId: token_id,
+            scheme,
+            ticks,
+            mass,
+            init_x: x,
+            init_y: y,
+            init_z: z,
+            init_vel_x: vel_x,
+            init_vel_y: vel_y,
+            init_vel_z: vel_z,
             final_x: final_system
                 .iter()
                 .map(|state| state.get_x())
@@ -143,6 +439,10 @@ impl RkFall {
                 .iter()
                 .map(|state| state.get_y())
                 .collect::<Vec<i64>>(),
+            final_z: final_system
+                .iter()
+                .map(|state| state.get_z())
+                .collect::<Vec<i64>>(),
             final_vel_x: final_system
                 .iter()
                 .map(|state| state.get_vel_x())
@@ -151,10 +451,60 @@ impl RkFall {
                 .iter()
                 .map(|state| state.get_vel_y())
                 .collect::<Vec<i64>>(),
+            final_vel_z: final_system
+                .iter()
+                .map(|state| state.get_vel_z())
+                .collect::<Vec<i64>>(),
         });
 
+        // tokens without a softening log were minted with plain gravity
+        if softening != 0 {
+            evm::log(RkFallSoftening {
+                tokenId: token_id,
+                softening,
+            });
+        }
+
         Ok(token_id)
     }
+
+    /// Conserved quantities of `bodies`, for example the initial or final system of a
+    /// token as logged in `RkFallMint`, under the gravity and softening it was minted
+    /// with at its scale and with the kinds of `kind`, as in `mint_with_options`. Returns
+    /// the kinetic, potential and total energy, the x and y momentum and the angular
+    /// momentum.
+    pub fn diagnostics(
+        decimals: u8,
+        grav_g: i64,
+        softening: i64,
+        kind: Vec<u8>,
+        bodies: Bodies,
+    ) -> CombinedResult<(i128, i128, i128, i128, i128, i128)> {
+        let (mass, x, y, vel_x, vel_y) = bodies;
+        check_lengths(mass.len(), &[x.len(), y.len(), vel_x.len(), vel_y.len()])?;
+        let params = PhysicsParams {
+            grav_g,
+            softening,
+            ..PhysicsParams::at_scale(scale_of(decimals)?)
+        };
+        let kinds = kinds_of(mass.len(), &kind)?;
+
+        let motion = [&x[..], &y[..], &vel_x[..], &vel_y[..]];
+        let d = match decimals {
+            6 => diagnostics_at::<1000000>(&params, &kinds, &mass, motion)?,
+            8 => diagnostics_at::<PRECISION>(&params, &kinds, &mass, motion)?,
+            10 => diagnostics_at::<10000000000>(&params, &kinds, &mass, motion)?,
+            _ => diagnostics_at::<1000000000000>(&params, &kinds, &mass, motion)?,
+        };
+        Ok((
+            d.kinetic_energy,
+            d.potential_energy,
+            d.total_energy,
+            d.momentum_x,
+            d.momentum_y,
+            d.angular_momentum,
+        ))
+    }
 }
 
 // #[entrypoint]
@@ -0,0 +1,474 @@
+use crate::int_rk4::{
+    centre_of_mass_frame, tick_many, tick_many_events, tick_many_force, tick_many_merging, Body,
+    BodyKind, CollisionRun, Event, EventDetector, FixedPoint, ForceLaw, MotionState, MotionState3D,
+    PhysicsParams, Scheme, SimError, DECIMALS, PRECISION,
+};
+
+use alloc::vec::Vec;
+use alloy_primitives::{keccak256, U256};
+use alloy_sol_types::{
+    encode,
+    sol_data::{Array, Bool, Int, Uint},
+    Encodable, SolType,
+};
+
+// What the contract does to mint a planar token short of storing and logging it: the
+// options are read, the bodies run and the token id taken, so that all of it can be
+// checked off-chain. Arrays are raw at the scale of the token, as in its logs.
+
+/// The mass, x, y, vel_x and vel_y arrays of a planar system, as logged in `RkFallMint`.
+pub type Bodies = (Vec<u64>, Vec<i64>, Vec<i64>, Vec<i64>, Vec<i64>);
+
+/// The mass, x, y, z, vel_x, vel_y and vel_z arrays of a system in three dimensions, as
+/// logged in `RkFallMint3D`.
+pub type Bodies3D = (
+    Vec<u64>,
+    Vec<i64>,
+    Vec<i64>,
+    Vec<i64>,
+    Vec<i64>,
+    Vec<i64>,
+    Vec<i64>,
+);
+
+/// The options a token is minted with, see `RkFall::mint_with_options`.
+pub type MintOptions = (
+    u8,      // decimals
+    u8,      // scheme
+    i64,     // grav_g
+    i64,     // time_period_sec
+    i64,     // softening
+    Vec<u8>, // force
+    Vec<u8>, // kind
+    i64,     // close_approach
+    bool,    // escapes
+    bool,    // apsides
+    bool,    // centre
+);
+
+/// Why a token cannot be minted, each of them an error of the contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MintError {
+    UnknownScheme(u8),
+    MismatchedLengths { bodies: usize, length: usize },
+    UnsupportedScale(u8),
+    UnknownForce,
+    UnknownKind(u8),
+    Sim(SimError),
+}
+
+impl From<SimError> for MintError {
+    fn from(err: SimError) -> Self {
+        MintError::Sim(err)
+    }
+}
+
+/// A planar token as the contract logs it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Minted {
+    pub token_id: U256,
+    pub mass: Vec<u64>,
+    /// The x, y, vel_x and vel_y arrays the bodies start from, centred if asked for.
+    pub init: [Vec<i64>; 4],
+    /// The same arrays for the final system.
+    pub last: [Vec<i64>; 4],
+    pub events: Vec<Event>,
+    /// The force law as logged in `RkFallForce`, empty for gravity.
+    pub force: Vec<u8>,
+    /// The kinds as logged in `RkFallKinds`, empty for free bodies only.
+    pub kind: Vec<u8>,
+    /// Whether the gravity and step are those tokens have always had at their scale,
+    /// which leaves out the `RkFallPhysics` log.
+    pub default_physics: bool,
+}
+
+/// Each body takes one value from every array, so arrays of differing lengths would
+/// leave bodies out of the simulation or run out of bounds.
+pub fn check_lengths(bodies: usize, lengths: &[usize]) -> Result<(), MintError> {
+    for &length in lengths {
+        if length != bodies {
+            return Err(MintError::MismatchedLengths { bodies, length });
+        }
+    }
+    Ok(())
+}
+
+/// Tokens minted with every default once told bodies apart by their masses, so that
+/// bodies of equal mass ignored each other. Only systems whose masses all differ still
+/// follow the run their original token id stands for.
+fn distinct_masses(mass: &[u64]) -> bool {
+    let mut sorted = mass.to_vec();
+    sorted.sort_unstable();
+    sorted.windows(2).all(|pair| pair[0] != pair[1])
+}
+
+/// The scale of `decimals` decimal places, for the precisions tokens can be minted at.
+pub fn scale_of(decimals: u8) -> Result<i64, MintError> {
+    match decimals {
+        6 => Ok(1000000),
+        8 => Ok(PRECISION),
+        10 => Ok(10000000000),
+        12 => Ok(1000000000000),
+        _ => Err(MintError::UnsupportedScale(decimals)),
+    }
+}
+
+/// The kinds of `bodies` bodies from their ids in `kind`, no ids at all being free bodies.
+pub fn kinds_of(bodies: usize, kind: &[u8]) -> Result<Vec<BodyKind>, MintError> {
+    if kind.is_empty() {
+        return Ok(alloc::vec![BodyKind::Free; bodies]);
+    }
+    check_lengths(bodies, &[kind.len()])?;
+    kind.iter()
+        .map(|&kind| BodyKind::from_id(kind).ok_or(MintError::UnknownKind(kind)))
+        .collect()
+}
+
+/// The planar system of the x, y, vel_x and vel_y arrays in `motion`, raw at a scale of
+/// `SCALE`.
+pub fn initial_system<const SCALE: i64>(
+    kinds: &[BodyKind],
+    mass: &[u64],
+    motion: [&[i64]; 4],
+) -> Vec<MotionState<FixedPoint<SCALE>>> {
+    let [x, y, vel_x, vel_y] = motion;
+
+    // bodies are identified by their index and so masses may repeat or be zero, as for
+    // tracers
+    let mut system = Vec::new();
+    for i in 0..mass.len() {
+        let state = MotionState::<FixedPoint<SCALE>>::from_raw(
+            i as u32, mass[i], x[i], y[i], vel_x[i], vel_y[i],
+        )
+        .with_kind(kinds[i]);
+        system.push(state);
+    }
+    system
+}
+
+/// The x, y, vel_x and vel_y arrays of a planar system, raw at its scale.
+fn motion_of<const SCALE: i64>(system: &[MotionState<FixedPoint<SCALE>>]) -> [Vec<i64>; 4] {
+    [
+        system.iter().map(|state| state.get_x()).collect(),
+        system.iter().map(|state| state.get_y()).collect(),
+        system.iter().map(|state| state.get_vel_x()).collect(),
+        system.iter().map(|state| state.get_vel_y()).collect(),
+    ]
+}
+
+/// The arrays of `motion` moved into the frame of the centre of mass of the system, see
+/// `centre_of_mass_frame`.
+fn centred<const SCALE: i64>(
+    kinds: &[BodyKind],
+    mass: &[u64],
+    motion: [&[i64]; 4],
+) -> Result<[Vec<i64>; 4], SimError> {
+    let system = initial_system::<SCALE>(kinds, mass, motion);
+    Ok(motion_of(&centre_of_mass_frame(&system)?))
+}
+
+/// Simulates a planar system at a scale of `SCALE` under `force`. `motion` holds the x, y, vel_x and
+/// vel_y arrays, and the same arrays are returned for the final system, all raw at
+/// that scale like the values of `params`, along with the events `detector` looks out for.
+fn simulate<const SCALE: i64>(
+    params: &PhysicsParams,
+    force: &ForceLaw,
+    detector: &EventDetector,
+    ticks: u32,
+    kinds: &[BodyKind],
+    mass: &[u64],
+    motion: [&[i64]; 4],
+) -> Result<([Vec<i64>; 4], Vec<Event>), SimError> {
+    let initial_system = initial_system::<SCALE>(kinds, mass, motion);
+
+    // looking out for nothing costs nothing
+    let (final_system, events) = if *detector == EventDetector::default() {
+        (
+            tick_many_force(params, force, ticks, &initial_system)?,
+            Vec::new(),
+        )
+    } else {
+        let run = tick_many_events(params, force, detector, ticks, &initial_system)?;
+        (run.system, run.events)
+    };
+    Ok((motion_of(&final_system), events))
+}
+
+/// Mints `bodies` for `ticks` ticks with `options`, as `RkFall::mint_with_options` does.
+pub fn minted(options: &MintOptions, bodies: Bodies, ticks: u32) -> Result<Minted, MintError> {
+    let &(
+        decimals,
+        scheme,
+        grav_g,
+        time_period_sec,
+        softening,
+        ref force,
+        ref kind,
+        close_approach,
+        escapes,
+        apsides,
+        centre,
+    ) = options;
+    let (mass, x, y, vel_x, vel_y) = bodies;
+    let tick_scheme = Scheme::from_id(scheme).ok_or(MintError::UnknownScheme(scheme))?;
+    let defaults = PhysicsParams::at_scale(scale_of(decimals)?);
+    let force_law = ForceLaw::decode(force).ok_or(MintError::UnknownForce)?;
+    check_lengths(mass.len(), &[x.len(), y.len(), vel_x.len(), vel_y.len()])?;
+    let kinds = kinds_of(mass.len(), kind)?;
+    let all_free = kinds.iter().all(|&kind| kind == BodyKind::Free);
+    let default_physics = grav_g == defaults.grav_g && time_period_sec == defaults.time_period_sec;
+
+    // the token id and logs take the force law and kinds as they are read rather than
+    // as they were spelt, so that gravity spelt out is no force and free bodies spelt
+    // out are no kinds, as for the tokens minted before either
+    let force = if force_law == ForceLaw::Gravity {
+        Vec::new()
+    } else {
+        force_law.encode()
+    };
+    let kind: Vec<u8> = if all_free {
+        Vec::new()
+    } else {
+        kinds.iter().map(|kind| kind.id()).collect()
+    };
+
+    // with every default the token id is that of the original mint, unless bodies
+    // share a mass and so move otherwise than they did there
+    let legacy = tick_scheme == Scheme::PerBodyRk4
+        && softening == 0
+        && decimals == DECIMALS
+        && default_physics
+        && force_law == ForceLaw::Gravity
+        && all_free
+        && !centre
+        && distinct_masses(&mass);
+
+    let params = PhysicsParams {
+        grav_g,
+        time_period_sec,
+        softening,
+        scheme: tick_scheme,
+    };
+    let detector = EventDetector {
+        close_approach,
+        escapes,
+        apsides,
+    };
+
+    // token id is taken from initial conditions and, unless the original mint's, from
+    // the options that change how the bodies move
+    let tokens = (
+        Array::<Uint<64>>::tokenize(&mass),
+        Array::<Int<64>>::tokenize(&x),
+        Array::<Int<64>>::tokenize(&y),
+        Array::<Int<64>>::tokenize(&vel_x),
+        Array::<Int<64>>::tokenize(&vel_y),
+        Uint::<32>::tokenize(&ticks),
+    );
+    let data = if legacy {
+        encode(&tokens)
+    } else {
+        let (mass, x, y, vel_x, vel_y, ticks) = tokens;
+        encode(&(
+            mass,
+            x,
+            y,
+            vel_x,
+            vel_y,
+            ticks,
+            Uint::<8>::tokenize(&decimals),
+            Uint::<8>::tokenize(&scheme),
+            Int::<64>::tokenize(&grav_g),
+            Int::<64>::tokenize(&time_period_sec),
+            Int::<64>::tokenize(&softening),
+            Array::<Uint<8>>::tokenize(&force),
+            Array::<Uint<8>>::tokenize(&kind),
+            Bool::tokenize(&centre),
+        ))
+    };
+    let token_id = keccak256(data).into();
+
+    // the bodies start from the centred system, if asked for, at the same scale
+    let motion = [&x[..], &y[..], &vel_x[..], &vel_y[..]];
+    let init = if centre {
+        match decimals {
+            6 => centred::<1000000>(&kinds, &mass, motion)?,
+            8 => centred::<PRECISION>(&kinds, &mass, motion)?,
+            10 => centred::<10000000000>(&kinds, &mass, motion)?,
+            _ => centred::<1000000000000>(&kinds, &mass, motion)?,
+        }
+    } else {
+        [x, y, vel_x, vel_y]
+    };
+
+    // compute the result, each scale being a separate copy of the integrators and
+    // `scale_of` having already rejected the unsupported ones
+    let [init_x, init_y, init_vel_x, init_vel_y] = &init;
+    let motion = [&init_x[..], &init_y[..], &init_vel_x[..], &init_vel_y[..]];
+    let (last, events) = match decimals {
+        6 => simulate::<1000000>(&params, &force_law, &detector, ticks, &kinds, &mass, motion)?,
+        8 => simulate::<PRECISION>(&params, &force_law, &detector, ticks, &kinds, &mass, motion)?,
+        10 => {
+            simulate::<10000000000>(&params, &force_law, &detector, ticks, &kinds, &mass, motion)?
+        }
+        _ => {
+            simulate::<1000000000000>(&params, &force_law, &detector, ticks, &kinds, &mass, motion)?
+        }
+    };
+
+    Ok(Minted {
+        token_id,
+        mass,
+        init,
+        last,
+        events,
+        force,
+        kind,
+        default_physics,
+    })
+}
+
+/// Mints `bodies` of the given radii for `ticks` ticks as `RkFall::mint_merging` does,
+/// returning the token id and the run. Merging bodies move under gravity alone, softened
+/// if asked for, with the gravity of 1 and step of 0.001 of tokens at 8 decimal places.
+/// They are all free bodies, none of the other options of `minted` apply, and no
+/// events are looked out for but their collisions.
+pub fn minted_merging(
+    scheme: u8,
+    softening: i64,
+    bodies: &Bodies,
+    radius: &[u64],
+    ticks: u32,
+) -> Result<(U256, CollisionRun<MotionState>), MintError> {
+    let (mass, x, y, vel_x, vel_y) = bodies;
+    let tick_scheme = Scheme::from_id(scheme).ok_or(MintError::UnknownScheme(scheme))?;
+    check_lengths(
+        mass.len(),
+        &[radius.len(), x.len(), y.len(), vel_x.len(), vel_y.len()],
+    )?;
+
+    // initial conditions, bodies are identified by their index
+    let mut initial_system = Vec::new();
+    for i in 0..mass.len() {
+        let state = MotionState::new(i as u32, mass[i], x[i], y[i], vel_x[i], vel_y[i])
+            .with_radius(radius[i]);
+        initial_system.push(state);
+    }
+
+    let params = PhysicsParams {
+        softening,
+        scheme: tick_scheme,
+        ..PhysicsParams::default()
+    };
+
+    // token id is taken from initial conditions
+    let tokens = (
+        Array::<Uint<64>>::tokenize(mass),
+        Array::<Int<64>>::tokenize(x),
+        Array::<Int<64>>::tokenize(y),
+        Array::<Int<64>>::tokenize(vel_x),
+        Array::<Int<64>>::tokenize(vel_y),
+        Uint::<32>::tokenize(&ticks),
+        Uint::<8>::tokenize(&scheme),
+        Encodable::<Array<Uint<64>>>::to_tokens(radius),
+    );
+    let data = if softening != 0 {
+        let (mass, x, y, vel_x, vel_y, ticks, scheme, radius) = tokens;
+        encode(&(
+            mass,
+            x,
+            y,
+            vel_x,
+            vel_y,
+            ticks,
+            scheme,
+            radius,
+            Int::<64>::tokenize(&softening),
+        ))
+    } else {
+        encode(&tokens)
+    };
+
+    Ok((
+        keccak256(data).into(),
+        tick_many_merging(&params, ticks, &initial_system)?,
+    ))
+}
+
+/// Mints `bodies` for `ticks` ticks as `RkFall::mint_3d` does, returning the token id and
+/// the final system. Bodies in three dimensions move under gravity alone, softened if
+/// asked for, with the gravity of 1 and step of 0.001 of tokens at 8 decimal places.
+/// They are all free bodies and none of the other options of `minted` apply.
+pub fn minted_3d(
+    scheme: u8,
+    softening: i64,
+    bodies: &Bodies3D,
+    ticks: u32,
+) -> Result<(U256, Vec<MotionState3D>), MintError> {
+    let (mass, x, y, z, vel_x, vel_y, vel_z) = bodies;
+    let tick_scheme = Scheme::from_id(scheme).ok_or(MintError::UnknownScheme(scheme))?;
+    check_lengths(
+        mass.len(),
+        &[
+            x.len(),
+            y.len(),
+            z.len(),
+            vel_x.len(),
+            vel_y.len(),
+            vel_z.len(),
+        ],
+    )?;
+
+    // initial conditions, bodies are identified by their index
+    let mut initial_system = Vec::new();
+    for i in 0..mass.len() {
+        let state = MotionState3D::new(
+            i as u32,
+            mass[i],
+            [x[i], y[i], z[i]],
+            [vel_x[i], vel_y[i], vel_z[i]],
+        );
+        initial_system.push(state);
+    }
+
+    let params = PhysicsParams {
+        softening,
+        scheme: tick_scheme,
+        ..PhysicsParams::default()
+    };
+
+    // token id is taken from initial conditions
+    let tokens = (
+        Array::<Uint<64>>::tokenize(mass),
+        Array::<Int<64>>::tokenize(x),
+        Array::<Int<64>>::tokenize(y),
+        Array::<Int<64>>::tokenize(z),
+        Array::<Int<64>>::tokenize(vel_x),
+        Array::<Int<64>>::tokenize(vel_y),
+        Array::<Int<64>>::tokenize(vel_z),
+        Uint::<32>::tokenize(&ticks),
+        Uint::<8>::tokenize(&scheme),
+    );
+    let data = if softening != 0 {
+        let (mass, x, y, z, vel_x, vel_y, vel_z, ticks, scheme) = tokens;
+        encode(&(
+            mass,
+            x,
+            y,
+            z,
+            vel_x,
+            vel_y,
+            vel_z,
+            ticks,
+            scheme,
+            Int::<64>::tokenize(&softening),
+        ))
+    } else {
+        encode(&tokens)
+    };
+
+    Ok((
+        keccak256(data).into(),
+        tick_many(&params, ticks, &initial_system)?,
+    ))
+}
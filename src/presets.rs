@@ -0,0 +1,250 @@
+use crate::int_rk4::{Body, BodyKind, MotionState};
+
+use alloc::vec::Vec;
+use serde::Serialize;
+
+// Systems worth minting, at the default scale of 8 decimal places under a gravity of 1
+// and a step of 0.001, as tokens are minted by default. Each is given with the number of
+// ticks that shows it off, which for the periodic orbits is one period.
+//
+// The presets mint.sh has always offered come first, exactly as it had them: the orbits
+// written at 4 decimal places and the three bodies with masses 1e-8 apart. The classic
+// problems follow, with initial conditions from the literature cut to the scale. A
+// periodic orbit cut like that only stays periodic for so long, and the Lagrange triangle
+// is unstable for equal masses and eventually comes apart. Last come the variants the
+// tests have always integrated instead, at 8 decimal places or with masses 1e-4 apart.
+
+/// A named system and the ticks to mint it for.
+#[derive(Debug, Clone, Serialize)]
+pub struct Preset {
+    pub name: &'static str,
+    pub ticks: u32,
+    pub system: Vec<MotionState>,
+}
+
+/// A preset of free bodies from rows of `[mass, x, y, vel_x, vel_y]`, ids following the
+/// order of the rows.
+fn free(name: &'static str, ticks: u32, bodies: &[[i64; 5]]) -> Preset {
+    let system = bodies
+        .iter()
+        .zip(0..)
+        .map(|([mass, x, y, vel_x, vel_y], id)| {
+            MotionState::new(id, *mass as u64, *x, *y, *vel_x, *vel_y)
+        })
+        .collect();
+    Preset {
+        name,
+        ticks,
+        system,
+    }
+}
+
+/// A light planet about a star, written at 4 decimal places as mint.sh has always had it.
+pub fn simple_orbit() -> Preset {
+    free(
+        "simple orbit",
+        1000,
+        &[[10000, 0, 0, 0, 0], [1, 0, 10000, 10000, 0]],
+    )
+}
+
+/// Three near equal masses that swing past each other and part.
+pub fn our_separate_ways() -> Preset {
+    free(
+        "our separate ways",
+        4000,
+        &[
+            [100000000, 0, 100000000, 30000000, 0],
+            [100000001, -100000000, -100000000, 0, 30000000],
+            [100000002, 100000000, -100000000, -30000000, 0],
+        ],
+    )
+}
+
+pub fn slow_dance() -> Preset {
+    free(
+        "slow dance",
+        8000,
+        &[
+            [100000000, 50000000, 50000000, 30000000, 0],
+            [100000001, -70000000, -50000000, 0, 30000000],
+            [100000002, -20000000, 60000000, 0, -30000000],
+        ],
+    )
+}
+
+/// Chenciner and Montgomery's figure of eight, as mint.sh has always had it. That puts the
+/// bodies a little off the published orbit, whose y is 0.24308753 and whose middle body
+/// moves at -0.93240737 along x.
+pub fn figure_of_eight() -> Preset {
+    free(
+        "figure of eight",
+        4000,
+        &[
+            [100000000, -97000436, 24208753, 46620368, 43236573],
+            [100000001, 0, 0, -93324973, -86473146],
+            [100000002, 97000436, -24208753, 46620368, 43236573],
+        ],
+    )
+}
+
+/// Two near equal masses about their centre of mass, written at 4 decimal places.
+pub fn double_orbit() -> Preset {
+    free(
+        "double orbit",
+        4000,
+        &[[40000, 0, 10000, 10000, 0], [40001, 0, -10000, -10000, 0]],
+    )
+}
+
+/// A star anchored at the origin with a ring of six massless tracers on circular orbits.
+pub fn debris_ring() -> Preset {
+    let mut preset = free(
+        "star with a debris ring",
+        4000,
+        &[
+            [100000000, 0, 0, 0, 0],
+            [0, 100000000, 0, 0, 100000000],
+            [0, 50000000, 86602540, -86602540, 50000000],
+            [0, -50000000, 86602540, -86602540, -50000000],
+            [0, -100000000, 0, 0, -100000000],
+            [0, -50000000, -86602540, 86602540, -50000000],
+            [0, 50000000, -86602540, 86602540, 50000000],
+        ],
+    );
+    for (i, body) in preset.system.iter_mut().enumerate() {
+        let kind = if i == 0 {
+            BodyKind::Anchor
+        } else {
+            BodyKind::Tracer
+        };
+        *body = body.with_kind(kind);
+    }
+    preset
+}
+
+/// Lagrange's equilateral triangle of equal masses, turning once about its centre.
+pub fn lagrange_equilateral() -> Preset {
+    free(
+        "lagrange equilateral",
+        8270,
+        &[
+            [100000000, 0, 100000000, -75983568, 0],
+            [100000000, -86602540, -50000000, 37991784, -65803700],
+            [100000000, 86602540, -50000000, 37991784, 65803700],
+        ],
+    )
+}
+
+/// The Pythagorean three-body problem posed by Burrau, masses of 3, 4 and 5 at rest on
+/// the corners of a 3-4-5 triangle. They fall together, meet in a series of close
+/// encounters and, long after these ticks, throw the lightest body out.
+pub fn pythagorean() -> Preset {
+    free(
+        "pythagorean",
+        10000,
+        &[
+            [300000000, 100000000, 300000000, 0, 0],
+            [400000000, -200000000, -100000000, 0, 0],
+            [500000000, 100000000, -100000000, 0, 0],
+        ],
+    )
+}
+
+/// Broucke's periodic orbit A1 of three equal masses, starting in a line.
+pub fn broucke_a1() -> Preset {
+    free(
+        "broucke a1",
+        6283,
+        &[
+            [100000000, -98926200, 0, 0, 191692441],
+            [100000000, 220961772, 0, 0, 19102687],
+            [100000000, -122035571, 0, 0, -210795129],
+        ],
+    )
+}
+
+/// Broucke's periodic orbit A2 of three equal masses, starting in a line.
+pub fn broucke_a2() -> Preset {
+    free(
+        "broucke a2",
+        7702,
+        &[
+            [100000000, 33613009, 0, 0, 153243153],
+            [100000000, 76998938, 0, 0, -62873509],
+            [100000000, -110611947, 0, 0, -90369643],
+        ],
+    )
+}
+
+/// The simple orbit at 8 decimal places, a circular orbit at a distance and speed of 1.
+pub fn simple_orbit_at_8_decimals() -> Preset {
+    free(
+        "simple orbit at 8 decimals",
+        1000,
+        &[[100000000, 0, 0, 0, 0], [10000, 0, 100000000, 100000000, 0]],
+    )
+}
+
+/// Our separate ways with masses 1e-4 apart.
+pub fn our_separate_ways_spread() -> Preset {
+    free(
+        "our separate ways, masses spread",
+        4000,
+        &[
+            [100000000, 0, 100000000, 30000000, 0],
+            [100010000, -100000000, -100000000, 0, 30000000],
+            [100020000, 100000000, -100000000, -30000000, 0],
+        ],
+    )
+}
+
+/// The figure of eight with masses 1e-4 apart.
+pub fn figure_of_eight_spread() -> Preset {
+    free(
+        "figure of eight, masses spread",
+        4000,
+        &[
+            [100000000, -97000436, 24208753, 46620368, 43236573],
+            [100010000, 0, 0, -93324973, -86473146],
+            [100020000, 97000436, -24208753, 46620368, 43236573],
+        ],
+    )
+}
+
+/// The double orbit at 8 decimal places, the heavier body first.
+pub fn double_orbit_at_8_decimals() -> Preset {
+    free(
+        "double orbit at 8 decimals",
+        4000,
+        &[
+            [400010000, 0, 100000000, 100000000, 0],
+            [400000000, 0, -100000000, -100000000, 0],
+        ],
+    )
+}
+
+/// Every preset, those of mint.sh first.
+pub fn presets() -> Vec<Preset> {
+    alloc::vec![
+        simple_orbit(),
+        our_separate_ways(),
+        slow_dance(),
+        figure_of_eight(),
+        double_orbit(),
+        debris_ring(),
+        lagrange_equilateral(),
+        pythagorean(),
+        broucke_a1(),
+        broucke_a2(),
+        simple_orbit_at_8_decimals(),
+        our_separate_ways_spread(),
+        figure_of_eight_spread(),
+        double_orbit_at_8_decimals(),
+    ]
+}
+
+/// The preset called `name`, if there is one.
+pub fn preset(name: &str) -> Option<Preset> {
+    presets().into_iter().find(|preset| preset.name == name)
+}
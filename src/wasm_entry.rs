@@ -1,12 +1,437 @@
-use crate::int_rk4::{tick, MotionState};
+use crate::diagnostics::{diagnostics, drift};
+use crate::int_rk4::{
+    centre_of_mass_frame, embedded_tableau, merge_collisions, tick, tick_adaptive, tick_barnes_hut,
+    tick_many_events, tick_merging, BarnesHut, Checkpoint, CheckpointError, EventDetector,
+    FixedPoint, ForceLaw, MotionState, MotionState3D, OrbitError, OrbitalElements, PhysicsParams,
+    SimError, StepControl, SystemBuilder, Trajectory, PRECISION,
+};
+use crate::presets::presets;
 use serde_wasm_bindgen::from_value;
 use serde_wasm_bindgen::to_value;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsValue;
 
+fn sim_error(err: SimError) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+fn checkpoint_error(err: CheckpointError) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+fn orbit_error(err: OrbitError) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+/// Advances `system` by one step under `params`, a `PhysicsParams` object.
 #[wasm_bindgen]
-pub fn tick_wasm(time_period_sec: i64, system: &JsValue) -> Result<JsValue, JsError> {
+pub fn tick_wasm(params: &JsValue, system: &JsValue) -> Result<JsValue, JsError> {
+    let params: PhysicsParams = from_value(params.clone())?;
     let system_ms: Vec<MotionState> = from_value(system.clone())?;
-    let res = tick(time_period_sec, &system_ms);
+    let res = tick(&params, &system_ms).map_err(sim_error)?;
     Ok(to_value(&res)?)
 }
+
+fn tick_at_scale<const SCALE: i64>(
+    params: &PhysicsParams,
+    system: &JsValue,
+) -> Result<JsValue, JsError> {
+    let system_ms: Vec<MotionState<FixedPoint<SCALE>>> = from_value(system.clone())?;
+    let res = tick(params, &system_ms).map_err(sim_error)?;
+    Ok(to_value(&res)?)
+}
+
+/// `tick_wasm` for a token minted with `decimals` decimal places, all values being
+/// raw at that scale.
+#[wasm_bindgen]
+pub fn tick_scaled_wasm(
+    decimals: u8,
+    params: &JsValue,
+    system: &JsValue,
+) -> Result<JsValue, JsError> {
+    let params: PhysicsParams = from_value(params.clone())?;
+    match decimals {
+        6 => tick_at_scale::<1000000>(&params, system),
+        8 => tick_at_scale::<PRECISION>(&params, system),
+        10 => tick_at_scale::<10000000000>(&params, system),
+        12 => tick_at_scale::<1000000000000>(&params, system),
+        _ => Err(JsError::new("unsupported scale")),
+    }
+}
+
+fn trajectory_at_scale<const SCALE: i64>(
+    params: &PhysicsParams,
+    force: ForceLaw,
+    ticks: u32,
+    stride: u32,
+    system: &JsValue,
+) -> Result<JsValue, JsError> {
+    let system_ms: Vec<MotionState<FixedPoint<SCALE>>> = from_value(system.clone())?;
+    let mut trajectory = Trajectory::new(*params, ticks, system_ms)
+        .with_stride(stride)
+        .with_force(force);
+    let mut samples = Vec::new();
+    while let Some(sample) = trajectory.next_sample() {
+        let (tick, system) = sample.map_err(sim_error)?;
+        samples.push((tick, system.to_vec()));
+    }
+    Ok(to_value(&samples)?)
+}
+
+/// The `[tick, system]` samples of a `Trajectory` over `ticks` ticks, for a token minted
+/// with `decimals` decimal places under the `ForceLaw` encoded in `force`, no bytes
+/// being gravity.
+#[wasm_bindgen]
+pub fn trajectory_wasm(
+    decimals: u8,
+    params: &JsValue,
+    force: &[u8],
+    ticks: u32,
+    stride: u32,
+    system: &JsValue,
+) -> Result<JsValue, JsError> {
+    let params: PhysicsParams = from_value(params.clone())?;
+    let force = ForceLaw::decode(force).ok_or_else(|| JsError::new("unknown force law"))?;
+    match decimals {
+        6 => trajectory_at_scale::<1000000>(&params, force, ticks, stride, system),
+        8 => trajectory_at_scale::<PRECISION>(&params, force, ticks, stride, system),
+        10 => trajectory_at_scale::<10000000000>(&params, force, ticks, stride, system),
+        12 => trajectory_at_scale::<1000000000000>(&params, force, ticks, stride, system),
+        _ => Err(JsError::new("unsupported scale")),
+    }
+}
+
+fn events_at_scale<const SCALE: i64>(
+    params: &PhysicsParams,
+    force: &ForceLaw,
+    detector: &EventDetector,
+    ticks: u32,
+    system: &JsValue,
+) -> Result<JsValue, JsError> {
+    let system_ms: Vec<MotionState<FixedPoint<SCALE>>> = from_value(system.clone())?;
+    let run = tick_many_events(params, force, detector, ticks, &system_ms).map_err(sim_error)?;
+    Ok(to_value(&run)?)
+}
+
+/// The `EventRun` of `ticks` ticks with the events `detector`, an `EventDetector`
+/// object, looks out for, for a token minted with `decimals` decimal places under the
+/// `ForceLaw` encoded in `force`.
+#[wasm_bindgen]
+pub fn events_wasm(
+    decimals: u8,
+    params: &JsValue,
+    force: &[u8],
+    detector: &JsValue,
+    ticks: u32,
+    system: &JsValue,
+) -> Result<JsValue, JsError> {
+    let params: PhysicsParams = from_value(params.clone())?;
+    let force = ForceLaw::decode(force).ok_or_else(|| JsError::new("unknown force law"))?;
+    let detector: EventDetector = from_value(detector.clone())?;
+    match decimals {
+        6 => events_at_scale::<1000000>(&params, &force, &detector, ticks, system),
+        8 => events_at_scale::<PRECISION>(&params, &force, &detector, ticks, system),
+        10 => events_at_scale::<10000000000>(&params, &force, &detector, ticks, system),
+        12 => events_at_scale::<1000000000000>(&params, &force, &detector, ticks, system),
+        _ => Err(JsError::new("unsupported scale")),
+    }
+}
+
+fn centre_at_scale<const SCALE: i64>(system: &JsValue) -> Result<JsValue, JsError> {
+    let system_ms: Vec<MotionState<FixedPoint<SCALE>>> = from_value(system.clone())?;
+    let centred = centre_of_mass_frame(&system_ms).map_err(sim_error)?;
+    Ok(to_value(&centred)?)
+}
+
+/// `system` moved into the frame of its centre of mass, as a token minted centred with
+/// `decimals` decimal places starts from.
+#[wasm_bindgen]
+pub fn centre_of_mass_frame_wasm(decimals: u8, system: &JsValue) -> Result<JsValue, JsError> {
+    match decimals {
+        6 => centre_at_scale::<1000000>(system),
+        8 => centre_at_scale::<PRECISION>(system),
+        10 => centre_at_scale::<10000000000>(system),
+        12 => centre_at_scale::<1000000000000>(system),
+        _ => Err(JsError::new("unsupported scale")),
+    }
+}
+
+/// Every `Preset`, with its name, ticks and system.
+#[wasm_bindgen]
+pub fn presets_wasm() -> Result<JsValue, JsError> {
+    Ok(to_value(&presets())?)
+}
+
+fn orbits_at_scale<const SCALE: i64>(
+    grav_g: i64,
+    mass: u64,
+    primaries: &[u32],
+    masses: &[u64],
+    elements: &JsValue,
+) -> Result<JsValue, JsError> {
+    let elements: Vec<OrbitalElements<FixedPoint<SCALE>>> = from_value(elements.clone())?;
+    if primaries.len() != elements.len() || masses.len() != elements.len() {
+        return Err(JsError::new("mismatched orbit arrays"));
+    }
+    let mut builder = SystemBuilder::new(grav_g, mass);
+    for i in 0..elements.len() {
+        builder
+            .orbiting(primaries[i], masses[i], &elements[i])
+            .map_err(orbit_error)?;
+    }
+    Ok(to_value(&builder.build())?)
+}
+
+/// A system for a token minted with `decimals` decimal places, built about a body of
+/// `mass` at the origin. Body `i + 1` has `masses[i]` and moves on `elements[i]`, an
+/// `OrbitalElements` object, about the body with id `primaries[i]`.
+#[wasm_bindgen]
+pub fn orbits_wasm(
+    decimals: u8,
+    grav_g: i64,
+    mass: u64,
+    primaries: &[u32],
+    masses: &[u64],
+    elements: &JsValue,
+) -> Result<JsValue, JsError> {
+    match decimals {
+        6 => orbits_at_scale::<1000000>(grav_g, mass, primaries, masses, elements),
+        8 => orbits_at_scale::<PRECISION>(grav_g, mass, primaries, masses, elements),
+        10 => orbits_at_scale::<10000000000>(grav_g, mass, primaries, masses, elements),
+        12 => orbits_at_scale::<1000000000000>(grav_g, mass, primaries, masses, elements),
+        _ => Err(JsError::new("unsupported scale")),
+    }
+}
+
+fn elements_at_scale<const SCALE: i64>(
+    grav_g: i64,
+    primary: &JsValue,
+    state: &JsValue,
+) -> Result<JsValue, JsError> {
+    let primary: MotionState<FixedPoint<SCALE>> = from_value(primary.clone())?;
+    let state: MotionState<FixedPoint<SCALE>> = from_value(state.clone())?;
+    let elements = OrbitalElements::from_state(grav_g, &primary, &state).map_err(orbit_error)?;
+    Ok(to_value(&elements)?)
+}
+
+/// The `OrbitalElements` of body `state` about body `primary`, for a token minted with
+/// `decimals` decimal places.
+#[wasm_bindgen]
+pub fn elements_wasm(
+    decimals: u8,
+    grav_g: i64,
+    primary: &JsValue,
+    state: &JsValue,
+) -> Result<JsValue, JsError> {
+    match decimals {
+        6 => elements_at_scale::<1000000>(grav_g, primary, state),
+        8 => elements_at_scale::<PRECISION>(grav_g, primary, state),
+        10 => elements_at_scale::<10000000000>(grav_g, primary, state),
+        12 => elements_at_scale::<1000000000000>(grav_g, primary, state),
+        _ => Err(JsError::new("unsupported scale")),
+    }
+}
+
+fn checkpoint_at_scale<const SCALE: i64>(
+    params: &PhysicsParams,
+    force: &ForceLaw,
+    tick: u32,
+    system: &JsValue,
+) -> Result<Vec<u8>, JsError> {
+    let system_ms: Vec<MotionState<FixedPoint<SCALE>>> = from_value(system.clone())?;
+    let checkpoint = Checkpoint {
+        params: *params,
+        force: force.clone(),
+        tick,
+        system: system_ms,
+    };
+    checkpoint.encode().map_err(sim_error)
+}
+
+/// The bytes of a `Checkpoint` of `system` at `tick`, for a token minted with `decimals`
+/// decimal places under the `ForceLaw` encoded in `force`.
+#[wasm_bindgen]
+pub fn checkpoint_wasm(
+    decimals: u8,
+    params: &JsValue,
+    force: &[u8],
+    tick: u32,
+    system: &JsValue,
+) -> Result<Vec<u8>, JsError> {
+    let params: PhysicsParams = from_value(params.clone())?;
+    let force = ForceLaw::decode(force).ok_or_else(|| JsError::new("unknown force law"))?;
+    match decimals {
+        6 => checkpoint_at_scale::<1000000>(&params, &force, tick, system),
+        8 => checkpoint_at_scale::<PRECISION>(&params, &force, tick, system),
+        10 => checkpoint_at_scale::<10000000000>(&params, &force, tick, system),
+        12 => checkpoint_at_scale::<1000000000000>(&params, &force, tick, system),
+        _ => Err(JsError::new("unsupported scale")),
+    }
+}
+
+fn resume_at_scale<const SCALE: i64>(bytes: &[u8], ticks: u32) -> Result<Vec<u8>, JsError> {
+    let checkpoint: Checkpoint<MotionState<FixedPoint<SCALE>>> =
+        Checkpoint::decode(bytes).map_err(checkpoint_error)?;
+    let res = checkpoint.resume(ticks).map_err(sim_error)?;
+    res.encode().map_err(sim_error)
+}
+
+/// Resumes the checkpoint `bytes` for another `ticks` ticks, returning the bytes of the
+/// checkpoint it reaches.
+#[wasm_bindgen]
+pub fn resume_wasm(decimals: u8, bytes: &[u8], ticks: u32) -> Result<Vec<u8>, JsError> {
+    match decimals {
+        6 => resume_at_scale::<1000000>(bytes, ticks),
+        8 => resume_at_scale::<PRECISION>(bytes, ticks),
+        10 => resume_at_scale::<10000000000>(bytes, ticks),
+        12 => resume_at_scale::<1000000000000>(bytes, ticks),
+        _ => Err(JsError::new("unsupported scale")),
+    }
+}
+
+fn decode_checkpoint_at_scale<const SCALE: i64>(bytes: &[u8]) -> Result<JsValue, JsError> {
+    let checkpoint: Checkpoint<MotionState<FixedPoint<SCALE>>> =
+        Checkpoint::decode(bytes).map_err(checkpoint_error)?;
+    Ok(to_value(&checkpoint)?)
+}
+
+/// The checkpoint `bytes` as a `{params, tick, system}` object.
+#[wasm_bindgen]
+pub fn decode_checkpoint_wasm(decimals: u8, bytes: &[u8]) -> Result<JsValue, JsError> {
+    match decimals {
+        6 => decode_checkpoint_at_scale::<1000000>(bytes),
+        8 => decode_checkpoint_at_scale::<PRECISION>(bytes),
+        10 => decode_checkpoint_at_scale::<10000000000>(bytes),
+        12 => decode_checkpoint_at_scale::<1000000000000>(bytes),
+        _ => Err(JsError::new("unsupported scale")),
+    }
+}
+
+#[wasm_bindgen]
+pub fn tick_3d_wasm(params: &JsValue, system: &JsValue) -> Result<JsValue, JsError> {
+    let params: PhysicsParams = from_value(params.clone())?;
+    let system_ms: Vec<MotionState3D> = from_value(system.clone())?;
+    let res = tick(&params, &system_ms).map_err(sim_error)?;
+    Ok(to_value(&res)?)
+}
+
+/// `tick_wasm` with gravity from a Barnes-Hut tree with an opening angle of `theta`,
+/// for systems too large for the direct sum.
+#[wasm_bindgen]
+pub fn tick_barnes_hut_wasm(
+    params: &JsValue,
+    theta: i64,
+    system: &JsValue,
+) -> Result<JsValue, JsError> {
+    let params: PhysicsParams = from_value(params.clone())?;
+    let system_ms: Vec<MotionState> = from_value(system.clone())?;
+    let res = tick_barnes_hut(&params, &BarnesHut { theta }, &system_ms).map_err(sim_error)?;
+    Ok(to_value(&res)?)
+}
+
+/// Merges the bodies of `system` that touch, returning the merged system along with
+/// the collisions, which are numbered `tick`.
+#[wasm_bindgen]
+pub fn merge_collisions_wasm(tick: u32, system: &JsValue) -> Result<JsValue, JsError> {
+    let system_ms: Vec<MotionState> = from_value(system.clone())?;
+    let res = merge_collisions(tick, &system_ms).map_err(sim_error)?;
+    Ok(to_value(&res)?)
+}
+
+#[wasm_bindgen]
+pub fn tick_merging_wasm(
+    params: &JsValue,
+    tick: u32,
+    system: &JsValue,
+) -> Result<JsValue, JsError> {
+    let params: PhysicsParams = from_value(params.clone())?;
+    let system_ms: Vec<MotionState> = from_value(system.clone())?;
+    let res = tick_merging(&params, tick, &system_ms).map_err(sim_error)?;
+    Ok(to_value(&res)?)
+}
+
+/// Integrates `system` forward by `target_time_sec` with the embedded pair `tableau`, 0
+/// being RKF45 and 1 Dormand-Prince, under `params` and the `ForceLaw` encoded in
+/// `force`. Returns the final system along with the accepted step sizes.
+#[wasm_bindgen]
+pub fn tick_adaptive_wasm(
+    params: &JsValue,
+    force: &[u8],
+    tableau: u8,
+    target_time_sec: i64,
+    control: &JsValue,
+    system: &JsValue,
+) -> Result<JsValue, JsError> {
+    let params: PhysicsParams = from_value(params.clone())?;
+    let force = ForceLaw::decode(force).ok_or_else(|| JsError::new("unknown force law"))?;
+    let tableau =
+        embedded_tableau(tableau).ok_or_else(|| JsError::new("unknown embedded tableau"))?;
+    let control: StepControl = from_value(control.clone())?;
+    let system_ms: Vec<MotionState> = from_value(system.clone())?;
+    let res = tick_adaptive(
+        &params,
+        &force,
+        tableau,
+        target_time_sec,
+        &control,
+        &system_ms,
+    )
+    .map_err(sim_error)?;
+    Ok(to_value(&res)?)
+}
+
+fn diagnostics_at_scale<const SCALE: i64>(
+    params: &PhysicsParams,
+    system: &JsValue,
+) -> Result<JsValue, JsError> {
+    let system_ms: Vec<MotionState<FixedPoint<SCALE>>> = from_value(system.clone())?;
+    let res = diagnostics(params, &system_ms).map_err(sim_error)?;
+    Ok(to_value(&res)?)
+}
+
+/// The `Diagnostics` of `system` for a token minted with `decimals` decimal places
+/// under `params`, a `PhysicsParams` object at that scale.
+#[wasm_bindgen]
+pub fn diagnostics_wasm(
+    decimals: u8,
+    params: &JsValue,
+    system: &JsValue,
+) -> Result<JsValue, JsError> {
+    let params: PhysicsParams = from_value(params.clone())?;
+    match decimals {
+        6 => diagnostics_at_scale::<1000000>(&params, system),
+        8 => diagnostics_at_scale::<PRECISION>(&params, system),
+        10 => diagnostics_at_scale::<10000000000>(&params, system),
+        12 => diagnostics_at_scale::<1000000000000>(&params, system),
+        _ => Err(JsError::new("unsupported scale")),
+    }
+}
+
+fn drift_at_scale<const SCALE: i64>(
+    params: &PhysicsParams,
+    before: &JsValue,
+    after: &JsValue,
+) -> Result<JsValue, JsError> {
+    let before_ms: Vec<MotionState<FixedPoint<SCALE>>> = from_value(before.clone())?;
+    let after_ms: Vec<MotionState<FixedPoint<SCALE>>> = from_value(after.clone())?;
+    let res = drift(params, &before_ms, &after_ms).map_err(sim_error)?;
+    Ok(to_value(&res)?)
+}
+
+/// The `Drift` from `before` to `after`, as for `diagnostics_wasm`.
+#[wasm_bindgen]
+pub fn drift_wasm(
+    decimals: u8,
+    params: &JsValue,
+    before: &JsValue,
+    after: &JsValue,
+) -> Result<JsValue, JsError> {
+    let params: PhysicsParams = from_value(params.clone())?;
+    match decimals {
+        6 => drift_at_scale::<1000000>(&params, before, after),
+        8 => drift_at_scale::<PRECISION>(&params, before, after),
+        10 => drift_at_scale::<10000000000>(&params, before, after),
+        12 => drift_at_scale::<1000000000000>(&params, before, after),
+        _ => Err(JsError::new("unsupported scale")),
+    }
+}
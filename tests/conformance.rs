@@ -0,0 +1,190 @@
+// The browser replays tokens with the wasm build of int_rk4 and the contract mints them
+// with the Stylus build, so every target has to step a system to the very same bits.
+// These tests hold int_rk4 to golden states of the mint.sh presets, recorded in
+// `conformance/vectors.json` with a chain of hashes of every body at every tick in between,
+// so that a target that strays is caught at the first tick and body it differs at. They run natively with `cargo test` and under node for
+// wasm32 with `wasm-pack test --node`, see `scripts/conformance.sh`. The Stylus build is
+// the same wasm32 code, so it is covered by the wasm32 run.
+
+#[cfg(test)]
+mod tests {
+    use rk_fall::int_rk4::{MotionState, PhysicsParams, Trajectory};
+    use rk_fall::presets::preset;
+    use serde::{Deserialize, Serialize};
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    /// A mint.sh preset minted with `params`, and its golden states. `hashes` holds the
+    /// hash chain of each body after each tick, 4 hex digits a body, tick after tick.
+    #[derive(Serialize, Deserialize)]
+    struct Vector {
+        preset: String,
+        params: PhysicsParams,
+        system: Vec<MotionState>,
+        states: Vec<State>,
+        hashes: String,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct State {
+        tick: u32,
+        system: Vec<MotionState>,
+    }
+
+    /// Chains of FNV-1a hashes of the bodies of a system, one a body, each taking in every
+    /// state its body has been in.
+    struct Chains(Vec<u64>);
+
+    impl Chains {
+        fn new(bodies: usize) -> Chains {
+            Chains(vec![0xcbf29ce484222325; bodies])
+        }
+
+        /// Takes in the bodies of `system` and returns the low 16 bits of each chain.
+        fn push(&mut self, system: &[MotionState]) -> Vec<String> {
+            self.0
+                .iter_mut()
+                .zip(system)
+                .map(|(hash, s)| {
+                    let bytes = [
+                        &(s.get_id() as u64).to_le_bytes()[..],
+                        &s.get_mass().to_le_bytes(),
+                        &s.get_radius().to_le_bytes(),
+                        &[u8::from(s.get_kind())],
+                        &s.get_x().to_le_bytes(),
+                        &s.get_y().to_le_bytes(),
+                        &s.get_vel_x().to_le_bytes(),
+                        &s.get_vel_y().to_le_bytes(),
+                    ];
+                    for byte in bytes.concat() {
+                        *hash = (*hash ^ byte as u64).wrapping_mul(0x100000001b3);
+                    }
+                    format!("{:04x}", *hash as u16)
+                })
+                .collect()
+        }
+    }
+
+    fn vectors() -> Vec<Vector> {
+        serde_json::from_str(include_str!("conformance/vectors.json")).unwrap()
+    }
+
+    /// Fails on the first body of `system` that differs from `expected`.
+    fn assert_state(vector: &Vector, tick: u32, system: &[MotionState], expected: &[MotionState]) {
+        assert_eq!(
+            system.len(),
+            expected.len(),
+            "{}: tick {} has {} bodies, expected {}",
+            vector.preset,
+            tick,
+            system.len(),
+            expected.len()
+        );
+        for (body, (s, e)) in system.iter().zip(expected).enumerate() {
+            let (s, e) = (
+                serde_json::to_value(s).unwrap(),
+                serde_json::to_value(e).unwrap(),
+            );
+            assert_eq!(
+                s, e,
+                "{}: tick {} first differs at body {}",
+                vector.preset, tick, body
+            );
+        }
+    }
+
+    /// Steps every vector with `tick`, checking the hashes after every tick and each golden
+    /// state on the way.
+    fn check_vectors(tick: impl Fn(&PhysicsParams, &Vec<MotionState>) -> Vec<MotionState>) {
+        for vector in &vectors() {
+            let preset = preset(&vector.preset).unwrap();
+            assert_state(vector, 0, &vector.system, &preset.system);
+            let mut system = vector.system.clone();
+            let mut chains = Chains::new(system.len());
+            let mut hashes = vector.hashes.as_str();
+            let mut ticks = 0;
+            for state in &vector.states {
+                while ticks < state.tick {
+                    system = tick(&vector.params, &system);
+                    ticks += 1;
+                    for (body, hash) in chains.push(&system).iter().enumerate() {
+                        let (expected, rest) = hashes.split_at(4);
+                        assert_eq!(
+                            hash, expected,
+                            "{}: tick {} first differs at body {}",
+                            vector.preset, ticks, body
+                        );
+                        hashes = rest;
+                    }
+                }
+                assert_state(vector, ticks, &system, &state.system);
+            }
+            assert!(hashes.is_empty());
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_conformance() {
+        check_vectors(|params, system| rk_fall::int_rk4::tick(params, system).unwrap());
+    }
+
+    /// The vectors through the bindings the browser calls.
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen_test]
+    fn test_conformance_tick_wasm() {
+        use rk_fall::wasm_entry::tick_wasm;
+        use serde_wasm_bindgen::{from_value, to_value};
+
+        check_vectors(|params, system| {
+            let res = tick_wasm(&to_value(params).unwrap(), &to_value(system).unwrap());
+            from_value(res.unwrap()).unwrap()
+        });
+    }
+
+    /// Rewrites the vectors from the native build, run with
+    /// `cargo test --test conformance -- --ignored` after a deliberate change to the
+    /// integration. Every other target must then agree with them. The presets recorded are
+    /// those already in the vectors.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    #[ignore]
+    fn bless_vectors() {
+        let params = PhysicsParams::default();
+        let vectors: Vec<Vector> = vectors()
+            .into_iter()
+            .map(|vector| {
+                let preset = preset(&vector.preset).unwrap();
+                let mut states = Vec::new();
+                let mut chains = Chains::new(preset.system.len());
+                let mut hashes = String::new();
+                let mut trajectory = Trajectory::new(params, preset.ticks, preset.system.clone());
+                while let Some(sample) = trajectory.next_sample() {
+                    let (tick, system) = sample.unwrap();
+                    if tick > 0 {
+                        hashes.extend(chains.push(system));
+                    }
+                    if [1, 10, 100, 1000, preset.ticks].contains(&tick) {
+                        states.push(State {
+                            tick,
+                            system: system.to_vec(),
+                        });
+                    }
+                }
+                Vector {
+                    preset: vector.preset,
+                    params,
+                    system: preset.system,
+                    states,
+                    hashes,
+                }
+            })
+            .collect();
+
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/conformance/vectors.json"
+        );
+        std::fs::write(path, serde_json::to_string_pretty(&vectors).unwrap() + "\n").unwrap();
+    }
+}
@@ -2,12 +2,46 @@
 mod tests {
     const PRECISION_FLOAT: f64 = PRECISION as f64;
 
+    use alloy_primitives::{keccak256, U256};
+    use alloy_sol_types::{
+        sol_data::{Array, Int, Uint},
+        SolType,
+    };
+    use fixed::types::I64F64;
     use plotters::{prelude::*, style::full_palette::ORANGE};
-    use rk_fall::int_rk4::{tick, MotionState, PRECISION};
+    use rk_fall::diagnostics::{
+        angular_momentum, diagnostics, drift, kinetic_energy, momentum, potential_energy,
+    };
+    use rk_fall::int_rk4::{
+        centre_of_mass, centre_of_mass_frame, div_sqrt, grav_rate_func, grav_rate_func_params,
+        grav_rate_func_softened, grav_rate_func_system_params, isqrt, mul_div_sqrt_cubed, rk4,
+        tick, tick_adaptive, tick_many, tick_many_barnes_hut, tick_many_events, tick_many_force,
+        tick_many_merging, tick_per_body, total_momentum, Anomaly, BarnesHut, Body, BodyKind,
+        Checkpoint, CheckpointError, Coulomb, Drag, Event, EventDetector, EventKind, Fixed,
+        FixedPoint, ForceLaw, ForceModel, Gravity, MotionState, MotionState3D, OrbitError,
+        OrbitalElements, PhysicsParams, PowerLaw, Scalar, Scheme, SimError, Spring, Springs,
+        StepControl, SystemBuilder, Trajectory, WideFixed, CHECKPOINT_VERSION, DORMAND_PRINCE,
+        PRECISION, RKF45, WIDE_PRECISION,
+    };
+    use rk_fall::mint::{minted, minted_3d, minted_merging, Bodies, Bodies3D, MintError, Minted};
+    use rk_fall::presets::{
+        double_orbit_at_8_decimals, figure_of_eight_spread, our_separate_ways_spread, presets,
+        simple_orbit_at_8_decimals, Preset,
+    };
+
+    /// A step of `time_period_sec` with `scheme` under unit gravity, unsoftened.
+    fn params(scheme: Scheme, time_period_sec: i64) -> PhysicsParams {
+        PhysicsParams {
+            time_period_sec,
+            scheme,
+            ..PhysicsParams::default()
+        }
+    }
 
     pub fn get_orbit_data(
         initial_states: Vec<MotionState>,
         time_periods: u32,
+        tick_func: fn(i64, &[MotionState]) -> Result<Vec<MotionState>, SimError>,
     ) -> Vec<Vec<(f64, f64)>> {
         let time_period_sec = (0.001 * PRECISION_FLOAT) as i64;
         let mut data: Vec<Vec<(f64, f64)>> = Vec::new();
@@ -50,7 +84,7 @@ mod tests {
         );
 
         for _ in 0..time_periods {
-            states = tick(time_period_sec, &states);
+            states = tick_func(time_period_sec, &states).unwrap();
             for (i, s) in states.iter().enumerate() {
                 data[i].push((s.get_x() as f64, s.get_y() as f64))
             }
@@ -80,34 +114,107 @@ mod tests {
         data
     }
 
-    fn init_stable_figure_eight() -> Vec<MotionState> {
-        let state0 = MotionState::new(
-            (1.0 * PRECISION_FLOAT) as u64,
-            (-0.97000436 * PRECISION_FLOAT) as i64,
-            (0.24208753 * PRECISION_FLOAT) as i64,
-            (0.4662036850 * PRECISION_FLOAT) as i64,
-            (0.4323657300 * PRECISION_FLOAT) as i64,
+    fn draw_chart(file_name: &str, data: Vec<Vec<(f64, f64)>>) {
+        let root = BitMapBackend::new(file_name, (480, 480)).into_drawing_area();
+        root.fill(&WHITE).unwrap();
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(5)
+            .x_label_area_size(30)
+            .y_label_area_size(30)
+            .build_cartesian_2d(-2f64..2f64, -2f64..2f64)
+            .unwrap();
+
+        chart.configure_mesh().draw().unwrap();
+
+        let colors = [RED, BLUE, GREEN, ORANGE];
+
+        for (i, series) in data.iter().enumerate() {
+            chart
+                .draw_series(series.iter().map(|&(x, y)| {
+                    Circle::new(
+                        (x / PRECISION_FLOAT, y / PRECISION_FLOAT),
+                        1,
+                        colors[i].filled(),
+                    )
+                }))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_single_orbit() {
+        draw_chart(
+            "./test_plots/single_orbit.png",
+            get_orbit_data(simple_orbit_at_8_decimals().system, 4000, tick_per_body),
         );
-        let state1 = MotionState::new(
-            (1.0001 * PRECISION_FLOAT) as u64,
-            (0.0 * PRECISION_FLOAT) as i64,
-            (0.0 * PRECISION_FLOAT) as i64,
-            (-0.933249737 * PRECISION_FLOAT) as i64,
-            (-0.86473146 * PRECISION_FLOAT) as i64,
+    }
+
+    #[test]
+    fn test_double_orbit() {
+        draw_chart(
+            "./test_plots/double_orbit.png",
+            get_orbit_data(double_orbit_at_8_decimals().system, 4000, tick_per_body),
         );
-        let state2 = MotionState::new(
-            (1.0002 * PRECISION_FLOAT) as u64,
-            (0.97000436 * PRECISION_FLOAT) as i64,
-            (-0.24208753 * PRECISION_FLOAT) as i64,
-            (0.4662036850 * PRECISION_FLOAT) as i64,
-            (0.4323657300 * PRECISION_FLOAT) as i64,
+    }
+
+    #[test]
+    fn test_our_separate_ways() {
+        draw_chart(
+            "./test_plots/our_separate_ways.png",
+            get_orbit_data(our_separate_ways_spread().system, 4000, tick_per_body),
         );
+    }
 
-        vec![state0, state1, state2]
+    #[test]
+    fn test_fig_eight() {
+        draw_chart(
+            "./test_plots/fig_eight.png",
+            get_orbit_data(figure_of_eight_spread().system, 4000, tick_per_body),
+        );
+    }
+
+    #[test]
+    fn test_fig_eight_coupled() {
+        draw_chart(
+            "./test_plots/fig_eight_coupled.png",
+            get_orbit_data(figure_of_eight_spread().system, 4000, |dt, s| {
+                tick(&params(Scheme::CoupledRk4, dt), s)
+            }),
+        );
+    }
+
+    #[test]
+    fn test_tableaus_free_body() {
+        // a lone body feels no force, so every tableau must move it exactly vel * t
+        let system = vec![MotionState::new(
+            0,
+            PRECISION as u64,
+            0,
+            0,
+            (0.5 * PRECISION_FLOAT) as i64,
+            (-0.25 * PRECISION_FLOAT) as i64,
+        )];
+        let time_period_sec = (0.001 * PRECISION_FLOAT) as i64;
+
+        for scheme in [
+            Scheme::Euler,
+            Scheme::Heun,
+            Scheme::Rk3,
+            Scheme::CoupledRk4,
+            Scheme::Rk38,
+        ] {
+            let end = tick_many(&params(scheme, time_period_sec), 1000, &system).unwrap();
+            assert_eq!(end[0].get_x(), (0.5 * PRECISION_FLOAT) as i64);
+            assert_eq!(end[0].get_y(), (-0.25 * PRECISION_FLOAT) as i64);
+            assert_eq!(end[0].get_vel_x(), system[0].get_vel_x());
+            assert_eq!(end[0].get_vel_y(), system[0].get_vel_y());
+        }
     }
 
-    fn single_orbit() -> Vec<MotionState> {
+    fn eccentric_orbit() -> Vec<MotionState> {
         let state0 = MotionState::new(
+            0,
             (1.0 * PRECISION_FLOAT) as u64,
             (0.0 * PRECISION_FLOAT) as i64,
             (0.0 * PRECISION_FLOAT) as i64,
@@ -115,118 +222,1954 @@ mod tests {
             (0.0 * PRECISION_FLOAT) as i64,
         );
         let state1 = MotionState::new(
+            1,
             (0.0001 * PRECISION_FLOAT) as u64,
             (0.0 * PRECISION_FLOAT) as i64,
             (1.0 * PRECISION_FLOAT) as i64,
-            (1.0 * PRECISION_FLOAT) as i64,
+            (0.3 * PRECISION_FLOAT) as i64,
             (0.0 * PRECISION_FLOAT) as i64,
         );
 
         vec![state0, state1]
     }
 
-    fn double_orbit() -> Vec<MotionState> {
-        let state0 = MotionState::new(
-            (4.0001 * PRECISION_FLOAT) as u64,
-            (0.0 * PRECISION_FLOAT) as i64,
-            (1.0 * PRECISION_FLOAT) as i64,
-            (1.0 * PRECISION_FLOAT) as i64,
-            (0.0 * PRECISION_FLOAT) as i64,
+    #[test]
+    fn test_adaptive_eccentric_orbit() {
+        let control = StepControl {
+            tolerance: 100,
+            initial_time_period_sec: (0.01 * PRECISION_FLOAT) as i64,
+            min_time_period_sec: (0.00001 * PRECISION_FLOAT) as i64,
+            max_time_period_sec: (0.05 * PRECISION_FLOAT) as i64,
+        };
+        let target_time_sec = (2.0 * PRECISION_FLOAT) as i64;
+        let params = PhysicsParams::default();
+
+        for tableau in [RKF45, DORMAND_PRINCE] {
+            let run = tick_adaptive(
+                &params,
+                &Gravity,
+                &tableau,
+                target_time_sec,
+                &control,
+                &eccentric_orbit(),
+            )
+            .unwrap();
+
+            assert_eq!(run.time_periods_sec.iter().sum::<i64>(), target_time_sec);
+            // the steps shrink through the close approach and grow again afterwards
+            let shortest = *run.time_periods_sec.iter().min().unwrap();
+            let longest = *run.time_periods_sec.iter().max().unwrap();
+            assert!(shortest < control.initial_time_period_sec);
+            assert!(longest > control.initial_time_period_sec);
+
+            // the run is fully determined by its inputs
+            let again = tick_adaptive(
+                &params,
+                &Gravity,
+                &tableau,
+                target_time_sec,
+                &control,
+                &eccentric_orbit(),
+            )
+            .unwrap();
+            assert_eq!(run.time_periods_sec, again.time_periods_sec);
+            assert_eq!(run.system[1].get_x(), again.system[1].get_x());
+        }
+
+        // the run follows the gravity and force law it is given
+        let stronger = PhysicsParams {
+            grav_g: 2 * PRECISION,
+            ..params
+        };
+        let run = |params: &PhysicsParams, force: &ForceLaw| {
+            tick_adaptive(
+                params,
+                force,
+                &DORMAND_PRINCE,
+                target_time_sec,
+                &control,
+                &eccentric_orbit(),
+            )
+            .unwrap()
+        };
+        let plain = run(&params, &ForceLaw::Gravity).system[1].get_x();
+        assert_ne!(run(&stronger, &ForceLaw::Gravity).system[1].get_x(), plain);
+        let drag = ForceLaw::Drag(Drag {
+            coefficient: PRECISION / 10,
+        });
+        let dragged = run(&params, &drag).system[1].get_x();
+        assert_ne!(dragged, plain);
+    }
+
+    fn orbit_radius(system: &[MotionState]) -> f64 {
+        let dx = (system[1].get_x() - system[0].get_x()) as f64 / PRECISION_FLOAT;
+        let dy = (system[1].get_y() - system[0].get_y()) as f64 / PRECISION_FLOAT;
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    #[test]
+    fn test_symplectic_circular_orbit() {
+        let time_period_sec = (0.001 * PRECISION_FLOAT) as i64;
+
+        for scheme in [Scheme::Leapfrog, Scheme::VelocityVerlet, Scheme::Yoshida4] {
+            let mut trajectory = Trajectory::new(
+                params(scheme, time_period_sec),
+                20000,
+                simple_orbit_at_8_decimals().system,
+            )
+            .with_stride(200);
+            while let Some(sample) = trajectory.next_sample() {
+                let (_, system) = sample.unwrap();
+                assert!((orbit_radius(system) - 1.0).abs() < 0.001);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fig_eight_yoshida() {
+        draw_chart(
+            "./test_plots/fig_eight_yoshida.png",
+            get_orbit_data(figure_of_eight_spread().system, 4000, |dt, s| {
+                tick(&params(Scheme::Yoshida4, dt), s)
+            }),
         );
-        let state1 = MotionState::new(
-            (4.0 * PRECISION_FLOAT) as u64,
-            (0.0 * PRECISION_FLOAT) as i64,
-            (-1.0 * PRECISION_FLOAT) as i64,
-            (-1.0 * PRECISION_FLOAT) as i64,
-            (0.0 * PRECISION_FLOAT) as i64,
+    }
+
+    #[test]
+    fn test_diagnostics_circular_orbit() {
+        let time_period_sec = (0.001 * PRECISION_FLOAT) as i64;
+        let system = simple_orbit_at_8_decimals().system;
+
+        // a light body on a clockwise unit circular orbit has kinetic energy m / 2,
+        // potential energy -m and angular momentum -m
+        let unit = PhysicsParams::default();
+        let start = diagnostics(&unit, &system).unwrap();
+        let mass = system[1].get_mass() as i128;
+        assert_eq!(start.kinetic_energy, mass / 2);
+        assert_eq!(start.potential_energy, -mass);
+        assert_eq!(start.angular_momentum, -mass);
+        assert_eq!((start.momentum_x, start.momentum_y), (mass, 0));
+
+        let euler = drift(
+            &unit,
+            &system,
+            &tick_many(&params(Scheme::Euler, time_period_sec), 4000, &system).unwrap(),
+        )
+        .unwrap();
+        let yoshida = drift(
+            &unit,
+            &system,
+            &tick_many(&params(Scheme::Yoshida4, time_period_sec), 4000, &system).unwrap(),
+        )
+        .unwrap();
+        let euler_energy = euler.total_energy.unwrap().abs();
+        let yoshida_energy = yoshida.total_energy.unwrap().abs();
+        assert!(yoshida_energy < euler_energy);
+        assert!(yoshida_energy < PRECISION as i128 / 1000);
+        assert!(yoshida.momentum_y.is_none());
+
+        // the potential follows the gravity of the run and its scale
+        let double = PhysicsParams {
+            grav_g: 2 * PRECISION,
+            ..unit
+        };
+        assert_eq!(
+            diagnostics(&double, &system).unwrap().potential_energy,
+            -2 * mass
         );
+        let scale = 1000000000000;
+        let fine: Vec<MotionState<FixedPoint<1000000000000>>> = system
+            .iter()
+            .map(|s| {
+                MotionState::from_raw(
+                    s.get_id(),
+                    s.get_mass() * 10000,
+                    s.get_x() * 10000,
+                    s.get_y() * 10000,
+                    s.get_vel_x() * 10000,
+                    s.get_vel_y() * 10000,
+                )
+            })
+            .collect();
+        let fine_start = diagnostics(&PhysicsParams::at_scale(scale), &fine).unwrap();
+        assert_eq!(fine_start.kinetic_energy, mass * 10000 / 2);
+        assert_eq!(fine_start.potential_energy, -mass * 10000);
+        assert_eq!(fine_start.angular_momentum, -mass * 10000);
 
-        vec![state0, state1]
+        // products too large even for i128 are overflows rather than panics
+        let fast = vec![
+            MotionState::new(0, u64::MAX, 0, 0, i64::MAX, i64::MAX),
+            MotionState::new(1, u64::MAX, i64::MIN, i64::MAX, 0, 0),
+            MotionState::new(2, u64::MAX, i64::MAX, i64::MIN, 0, 0),
+        ];
+        assert_eq!(kinetic_energy(&fast).unwrap_err(), SimError::Overflow);
+        assert_eq!(
+            potential_energy(&unit, &fast[1..]).unwrap_err(),
+            SimError::Overflow
+        );
+        assert_eq!(
+            potential_energy(&unit, &fast[..2]).unwrap_err(),
+            SimError::Overflow
+        );
+        let spinning = [MotionState::new(
+            0,
+            2,
+            i64::MAX,
+            i64::MIN,
+            i64::MAX,
+            i64::MAX,
+        )];
+        assert_eq!(angular_momentum(&spinning).unwrap_err(), SimError::Overflow);
+        assert_eq!(diagnostics(&unit, &fast).unwrap_err(), SimError::Overflow);
     }
 
-    fn our_separate_ways() -> Vec<MotionState> {
-        let state0 = MotionState::new(
-            (1.0 * PRECISION_FLOAT) as u64,
-            (0.0 * PRECISION_FLOAT) as i64,
-            (1.0 * PRECISION_FLOAT) as i64,
-            (0.3 * PRECISION_FLOAT) as i64,
-            (0.0 * PRECISION_FLOAT) as i64,
+    #[test]
+    fn test_diagnostics_debris_ring() {
+        let unit = PhysicsParams::default();
+
+        // give the tracers some mass and set the anchor moving, away from the origin
+        let mut ring: Vec<MotionState> = debris_ring(8)
+            .iter()
+            .map(|s| s.with_mass(PRECISION as u64 / 100, 0))
+            .collect();
+        ring[0] = MotionState::new(0, PRECISION as u64, PRECISION / 10, 0, 0, PRECISION)
+            .with_kind(BodyKind::Anchor);
+        let tracers = &ring[1..];
+
+        // an anchor carries no kinetic energy or momentum, whatever its velocity
+        let start = diagnostics(&unit, &ring).unwrap();
+        assert_eq!(start.kinetic_energy, kinetic_energy(tracers).unwrap());
+        assert_eq!(
+            (start.momentum_x, start.momentum_y),
+            momentum(tracers).unwrap()
         );
-        let state1 = MotionState::new(
-            (1.0001 * PRECISION_FLOAT) as u64,
-            (-1.0 * PRECISION_FLOAT) as i64,
-            (-1.0 * PRECISION_FLOAT) as i64,
-            (0.0 * PRECISION_FLOAT) as i64,
-            (0.3 * PRECISION_FLOAT) as i64,
+        assert_eq!(start.angular_momentum, angular_momentum(tracers).unwrap());
+        assert!(start.kinetic_energy > 0);
+
+        // tracers have potential energy towards the anchor alone
+        let pairs: i128 = tracers
+            .iter()
+            .map(|t| potential_energy(&unit, &[ring[0].clone(), t.clone()]).unwrap())
+            .sum();
+        assert_eq!(start.potential_energy, pairs);
+        assert_eq!(potential_energy(&unit, tracers).unwrap(), 0);
+        let free: Vec<MotionState> = ring.iter().map(|s| s.with_kind(BodyKind::Free)).collect();
+        assert!(diagnostics(&unit, &free).unwrap().potential_energy < pairs);
+    }
+
+    #[test]
+    fn test_3d_matches_2d() {
+        let time_period_sec = (0.001 * PRECISION_FLOAT) as i64;
+        let system = figure_of_eight_spread().system;
+
+        // the same orbit laid in the xy plane and in the yz plane of 3D space
+        let flat: Vec<MotionState3D> = system
+            .iter()
+            .map(|s| {
+                MotionState3D::new(
+                    s.get_id(),
+                    s.get_mass(),
+                    [s.get_x(), s.get_y(), 0],
+                    [s.get_vel_x(), s.get_vel_y(), 0],
+                )
+            })
+            .collect();
+        let upright: Vec<MotionState3D> = system
+            .iter()
+            .map(|s| {
+                MotionState3D::new(
+                    s.get_id(),
+                    s.get_mass(),
+                    [0, s.get_x(), s.get_y()],
+                    [0, s.get_vel_x(), s.get_vel_y()],
+                )
+            })
+            .collect();
+
+        for id in 0..=8 {
+            let scheme = Scheme::from_id(id).unwrap();
+            let expected = tick_many(&params(scheme, time_period_sec), 500, &system).unwrap();
+            let flat = tick_many(&params(scheme, time_period_sec), 500, &flat).unwrap();
+            let upright = tick_many(&params(scheme, time_period_sec), 500, &upright).unwrap();
+
+            for ((e, f), u) in expected.iter().zip(&flat).zip(&upright) {
+                assert_eq!(
+                    (e.get_x(), e.get_y(), 0, e.get_vel_x(), e.get_vel_y(), 0),
+                    (
+                        f.get_x(),
+                        f.get_y(),
+                        f.get_z(),
+                        f.get_vel_x(),
+                        f.get_vel_y(),
+                        f.get_vel_z()
+                    )
+                );
+                assert_eq!(
+                    (0, e.get_x(), e.get_y(), 0, e.get_vel_x(), e.get_vel_y()),
+                    (
+                        u.get_x(),
+                        u.get_y(),
+                        u.get_z(),
+                        u.get_vel_x(),
+                        u.get_vel_y(),
+                        u.get_vel_z()
+                    )
+                );
+            }
+        }
+
+        // the contract runs them under gravity at the defaults of 8 decimal places, the
+        // scheme and softening being all it takes
+        let bodies: Bodies3D = (
+            flat.iter().map(|s| s.get_mass()).collect(),
+            flat.iter().map(|s| s.get_x()).collect(),
+            flat.iter().map(|s| s.get_y()).collect(),
+            flat.iter().map(|s| s.get_z()).collect(),
+            flat.iter().map(|s| s.get_vel_x()).collect(),
+            flat.iter().map(|s| s.get_vel_y()).collect(),
+            flat.iter().map(|s| s.get_vel_z()).collect(),
         );
-        let state2 = MotionState::new(
-            (1.0002 * PRECISION_FLOAT) as u64,
-            (1.0 * PRECISION_FLOAT) as i64,
-            (-1.0 * PRECISION_FLOAT) as i64,
-            (-0.3 * PRECISION_FLOAT) as i64,
-            (0.0 * PRECISION_FLOAT) as i64,
+        let scheme = Scheme::Yoshida4.id();
+        let (token_id, minted) = minted_3d(scheme, 0, &bodies, 500).unwrap();
+        let expected = tick_many(&params(Scheme::Yoshida4, time_period_sec), 500, &flat).unwrap();
+        for (e, m) in expected.iter().zip(&minted) {
+            assert_eq!(
+                (
+                    e.get_x(),
+                    e.get_y(),
+                    e.get_z(),
+                    e.get_vel_x(),
+                    e.get_vel_y()
+                ),
+                (
+                    m.get_x(),
+                    m.get_y(),
+                    m.get_z(),
+                    m.get_vel_x(),
+                    m.get_vel_y()
+                )
+            );
+        }
+        let (softened, _) = minted_3d(scheme, PRECISION / 100, &bodies, 500).unwrap();
+        assert_ne!(softened, token_id);
+        assert_eq!(
+            minted_3d(9, 0, &bodies, 500).unwrap_err(),
+            MintError::UnknownScheme(9)
         );
+    }
 
-        vec![state0, state1, state2]
+    #[test]
+    fn test_equal_masses_attract() {
+        let time_period_sec = (0.001 * PRECISION_FLOAT) as i64;
+        let system = vec![
+            MotionState::new(0, PRECISION as u64, -PRECISION, 0, 0, 0),
+            MotionState::new(1, PRECISION as u64, PRECISION, 0, 0, 0),
+        ];
+
+        let end = tick_many(&params(Scheme::CoupledRk4, time_period_sec), 100, &system).unwrap();
+        assert!(end[0].get_vel_x() > 0);
+        assert_eq!(end[0].get_vel_x(), -end[1].get_vel_x());
+        assert_eq!(end[0].get_x(), -end[1].get_x());
     }
 
-    fn draw_chart(file_name: &str, data: Vec<Vec<(f64, f64)>>) {
-        let root = BitMapBackend::new(file_name, (480, 480)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
+    #[test]
+    fn test_fig_eight_equal_masses() {
+        let time_period_sec = (0.001 * PRECISION_FLOAT) as i64;
+        let system: Vec<MotionState> = figure_of_eight_spread()
+            .system
+            .iter()
+            .map(|s| {
+                MotionState::new(
+                    s.get_id(),
+                    PRECISION as u64,
+                    s.get_x(),
+                    s.get_y(),
+                    s.get_vel_x(),
+                    s.get_vel_y(),
+                )
+            })
+            .collect();
 
-        let mut chart = ChartBuilder::on(&root)
-            .margin(5)
-            .x_label_area_size(30)
-            .y_label_area_size(30)
-            .build_cartesian_2d(-2f64..2f64, -2f64..2f64)
+        // after one period of about 6.3259 every body is back where it started
+        let end = tick_many(&params(Scheme::Yoshida4, time_period_sec), 6326, &system).unwrap();
+        for (s, e) in system.iter().zip(&end) {
+            let dx = (e.get_x() - s.get_x()) as f64 / PRECISION_FLOAT;
+            let dy = (e.get_y() - s.get_y()) as f64 / PRECISION_FLOAT;
+            assert!((dx * dx + dy * dy).sqrt() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_merging_head_on() {
+        let time_period_sec = (0.001 * PRECISION_FLOAT) as i64;
+        let radius = (0.1 * PRECISION_FLOAT) as u64;
+        let system = vec![
+            MotionState::new(0, PRECISION as u64, -PRECISION, 0, PRECISION / 2, 0)
+                .with_radius(radius),
+            MotionState::new(1, (PRECISION / 2) as u64, PRECISION, 0, -PRECISION, 0)
+                .with_radius(radius),
+        ];
+
+        let run =
+            tick_many_merging(&params(Scheme::CoupledRk4, time_period_sec), 2000, &system).unwrap();
+        assert_eq!(run.collisions.len(), 1);
+        assert_eq!((run.collisions[0].id, run.collisions[0].other_id), (0, 1));
+        assert!(run.collisions[0].tick > 0);
+
+        // one body is left, carrying all the mass and momentum and the combined area
+        assert_eq!(run.system.len(), 1);
+        let merged = &run.system[0];
+        assert_eq!(merged.get_id(), 0);
+        assert_eq!(
+            merged.get_mass(),
+            system[0].get_mass() + system[1].get_mass()
+        );
+        assert_eq!(
+            merged.get_radius(),
+            (radius as f64 * 2f64.sqrt()).floor() as u64
+        );
+        let (before, _) = momentum(&system).unwrap();
+        let (after, _) = momentum(&run.system).unwrap();
+        assert!((after - before).abs() < PRECISION as i128 / 100000);
+        assert_eq!(merged.get_y(), 0);
+        assert_eq!(merged.get_vel_y(), 0);
+
+        // the contract merges them under gravity at the defaults of 8 decimal places,
+        // the scheme and softening being all it takes
+        let bodies: Bodies = (
+            system.iter().map(|s| s.get_mass()).collect(),
+            system.iter().map(|s| s.get_x()).collect(),
+            system.iter().map(|s| s.get_y()).collect(),
+            system.iter().map(|s| s.get_vel_x()).collect(),
+            system.iter().map(|s| s.get_vel_y()).collect(),
+        );
+        let scheme = Scheme::CoupledRk4.id();
+        let (token_id, minted) =
+            minted_merging(scheme, 0, &bodies, &[radius, radius], 2000).unwrap();
+        assert_same_system(&minted.system, &run.system);
+        assert_eq!(minted.collisions.len(), 1);
+        let (softened, _) =
+            minted_merging(scheme, PRECISION / 100, &bodies, &[radius, radius], 2000).unwrap();
+        assert_ne!(softened, token_id);
+        assert_eq!(
+            minted_merging(scheme, 0, &bodies, &[radius], 2000).unwrap_err(),
+            MintError::MismatchedLengths {
+                bodies: 2,
+                length: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_merging_coincident_points() {
+        let time_period_sec = (0.001 * PRECISION_FLOAT) as i64;
+        let system = vec![
+            MotionState::new(0, PRECISION as u64, 0, 0, 0, PRECISION),
+            MotionState::new(1, PRECISION as u64, 0, 0, 0, -PRECISION),
+            MotionState::new(2, PRECISION as u64, 0, 3 * PRECISION, 0, 0),
+        ];
+
+        // points merge before they can divide by a zero distance
+        let run =
+            tick_many_merging(&params(Scheme::Yoshida4, time_period_sec), 10, &system).unwrap();
+        assert_eq!(run.collisions.len(), 1);
+        assert_eq!(run.collisions[0].tick, 0);
+        assert_eq!(run.system.len(), 2);
+        assert_eq!(run.system[0].get_mass(), 2 * PRECISION as u64);
+        assert_eq!(run.system[0].get_radius(), 0);
+        assert_eq!(run.collisions[0].merged.get_vel_y(), 0);
+        assert_eq!(run.system[1].get_id(), 2);
+    }
+
+    #[test]
+    fn test_softening() {
+        let softening = PRECISION / 10;
+        let system = vec![
+            MotionState::new(0, PRECISION as u64, 0, 0, 0, 0),
+            MotionState::new(1, PRECISION as u64, softening, 0, 0, 0),
+        ];
+
+        // at a distance of the softening length the pull drops from 1 / r^2 to
+        // 1 / (2 * sqrt(2) * r^2)
+        let plain = grav_rate_func(&system[1], &system).unwrap();
+        assert_eq!(plain.get_vel_x(), -100 * PRECISION);
+        let softened = grav_rate_func_softened(&system[1], &system, softening).unwrap();
+        let expected = -1.0 / (2.0 * 2f64.sqrt() * 0.01);
+        assert!((softened.get_vel_x() as f64 / PRECISION_FLOAT - expected).abs() < 0.001);
+        assert_eq!(softened.get_vel_y(), 0);
+
+        // coincident bodies no longer divide by zero
+        let time_period_sec = (0.001 * PRECISION_FLOAT) as i64;
+        let coincident = vec![
+            MotionState::new(0, PRECISION as u64, 0, 0, 0, 0),
+            MotionState::new(1, PRECISION as u64, 0, 0, 0, 0),
+        ];
+        for id in 0..=8 {
+            let scheme = Scheme::from_id(id).unwrap();
+            let end = tick_many(
+                &PhysicsParams {
+                    softening,
+                    ..params(scheme, time_period_sec)
+                },
+                100,
+                &coincident,
+            )
             .unwrap();
+            for state in &end {
+                assert_eq!((state.get_x(), state.get_y()), (0, 0));
+                assert_eq!((state.get_vel_x(), state.get_vel_y()), (0, 0));
+            }
+        }
+    }
 
-        chart.configure_mesh().draw().unwrap();
+    #[test]
+    fn test_physics_params() {
+        // doubling gravity is the same as doubling every mass
+        let system = simple_orbit_at_8_decimals().system;
+        let heavier: Vec<MotionState> = system
+            .iter()
+            .map(|s| {
+                MotionState::new(
+                    s.get_id(),
+                    s.get_mass() * 2,
+                    s.get_x(),
+                    s.get_y(),
+                    s.get_vel_x(),
+                    s.get_vel_y(),
+                )
+            })
+            .collect();
+        for id in 0..=8 {
+            let scheme = Scheme::from_id(id).unwrap();
+            let double_g = PhysicsParams {
+                grav_g: 2 * PRECISION,
+                ..params(scheme, PRECISION / 1000)
+            };
+            let end = tick_many(&double_g, 500, &system).unwrap();
+            let expected = tick_many(&params(scheme, PRECISION / 1000), 500, &heavier).unwrap();
+            for (s, e) in end.iter().zip(&expected) {
+                assert_eq!((s.get_x(), s.get_y()), (e.get_x(), e.get_y()));
+                assert_eq!(
+                    (s.get_vel_x(), s.get_vel_y()),
+                    (e.get_vel_x(), e.get_vel_y())
+                );
+            }
+        }
 
-        let colors = vec![RED, BLUE, GREEN, ORANGE];
+        // half the step over twice the ticks covers the same time
+        let coarse = tick_many(&params(Scheme::CoupledRk4, PRECISION / 1000), 1000, &system);
+        let fine = tick_many(&params(Scheme::CoupledRk4, PRECISION / 2000), 2000, &system);
+        for (c, f) in coarse.unwrap().iter().zip(&fine.unwrap()) {
+            assert!((c.get_x() - f.get_x()).abs() < PRECISION / 10000);
+            assert!((c.get_y() - f.get_y()).abs() < PRECISION / 10000);
+        }
 
-        for (i, series) in data.iter().enumerate() {
-            chart
-                .draw_series(series.iter().map(|&(x, y)| {
-                    Circle::new(
-                        (x / PRECISION_FLOAT, y / PRECISION_FLOAT),
-                        1,
-                        colors[i].filled(),
-                    )
-                }))
-                .unwrap();
+        // the scheme is carried by its id
+        let json = r#"{"grav_g":100000000,"time_period_sec":100000,"softening":0,"scheme":1}"#;
+        let parsed: PhysicsParams = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed, params(Scheme::CoupledRk4, PRECISION / 1000));
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+        assert!(serde_json::from_str::<PhysicsParams>(&json.replace(":1}", ":9}")).is_err());
+    }
+
+    /// A pseudo-random cluster of `n` equal bodies at rest within a unit of the origin,
+    /// flat when `flat`.
+    fn cluster(n: u32, flat: bool) -> Vec<MotionState3D> {
+        let mut seed: u64 = 1;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as i64 % (2 * PRECISION) - PRECISION
+        };
+        (0..n)
+            .map(|id| {
+                let (x, y) = (next(), next());
+                let z = if flat { 0 } else { next() };
+                MotionState3D::new(id, (PRECISION / 100) as u64, [x, y, z], [0; 3])
+            })
+            .collect()
+    }
+
+    fn flatten(system: &Vec<MotionState3D>) -> Vec<MotionState> {
+        system
+            .iter()
+            .map(|s| {
+                let [x, y, _] = s.get_position();
+                let [vel_x, vel_y, _] = s.get_velocity();
+                MotionState::new(
+                    s.get_id(),
+                    s.get_mass(),
+                    x.raw(),
+                    y.raw(),
+                    vel_x.raw(),
+                    vel_y.raw(),
+                )
+            })
+            .collect()
+    }
+
+    fn compare_barnes_hut<B: Body>(system: &[B], params: &PhysicsParams) {
+        let direct = grav_rate_func_system_params(system, params).unwrap();
+
+        // an opening angle of 0 opens every cell, leaving the direct sum
+        let exact = BarnesHut { theta: 0 }
+            .rate_func_system(system, params)
+            .unwrap();
+        for (e, d) in exact.iter().zip(&direct) {
+            assert_eq!(e.get_velocity(), d.get_velocity());
         }
+
+        let approximate = BarnesHut {
+            theta: PRECISION / 2,
+        }
+        .rate_func_system(system, params)
+        .unwrap();
+        let (mut error, mut total) = (0.0, 0.0);
+        for (a, d) in approximate.iter().zip(&direct) {
+            assert_eq!(a.get_position(), d.get_position());
+            for axis in 0..B::DIMENSIONS {
+                let a = a.get_velocity()[axis].to_scaled() as f64;
+                let d = d.get_velocity()[axis].to_scaled() as f64;
+                error += (a - d).abs();
+                total += d.abs();
+            }
+        }
+        assert!(error / total < 0.01);
     }
 
     #[test]
-    fn test_single_orbit() {
-        draw_chart(
-            "./test_plots/single_orbit.png",
-            get_orbit_data(single_orbit(), 4000),
+    fn test_barnes_hut() {
+        let params = PhysicsParams {
+            softening: PRECISION / 100,
+            ..PhysicsParams::default()
+        };
+
+        // a quadtree for planar bodies, an octree in 3D
+        compare_barnes_hut(&flatten(&cluster(300, true)), &params);
+        compare_barnes_hut(&cluster(300, false), &params);
+
+        // coincident bodies share a cell
+        let mut crowded = cluster(20, false);
+        crowded.push(MotionState3D::new(20, PRECISION as u64, [0; 3], [0; 3]));
+        crowded.push(MotionState3D::new(21, PRECISION as u64, [0; 3], [0; 3]));
+        compare_barnes_hut(&crowded, &params);
+
+        // with every cell opened a run matches the direct sum, per-body scheme included
+        let system = cluster(30, false);
+        for scheme in [Scheme::PerBodyRk4, Scheme::Yoshida4] {
+            let params = PhysicsParams { scheme, ..params };
+            let tree = tick_many_barnes_hut(&params, &BarnesHut { theta: 0 }, 20, &system);
+            let direct = tick_many(&params, 20, &system).unwrap();
+            for (t, d) in tree.unwrap().iter().zip(&direct) {
+                assert_eq!(t.get_position(), d.get_position());
+                assert_eq!(t.get_velocity(), d.get_velocity());
+            }
+        }
+    }
+
+    fn compare_pairwise<B: Body>(system: &[B], params: &PhysicsParams) {
+        let pairwise = grav_rate_func_system_params(system, params).unwrap();
+        for (state, rate) in system.iter().zip(&pairwise) {
+            let reference = grav_rate_func_params(state, system, params).unwrap();
+            assert_eq!(rate.get_position(), reference.get_position());
+            assert_eq!(rate.get_velocity(), reference.get_velocity());
+        }
+    }
+
+    #[test]
+    fn test_pairwise_rates() {
+        // visiting each pair once keeps the reference ordering bit for bit, whatever
+        // the backend
+        let params = PhysicsParams {
+            grav_g: 3 * PRECISION,
+            softening: PRECISION / 100,
+            ..PhysicsParams::default()
+        };
+        let system = cluster(50, false);
+        compare_pairwise(&system, &params);
+        compare_pairwise(&flatten(&cluster(50, true)), &params);
+        fn convert<S: Scalar>(system: &Vec<MotionState3D>) -> Vec<MotionState3D<S>> {
+            system.iter().map(|s| s.convert().unwrap()).collect()
+        }
+        compare_pairwise(&convert::<WideFixed>(&system), &params);
+        compare_pairwise(&convert::<I64F64>(&system), &params);
+        compare_pairwise(&convert::<f64>(&system), &params);
+
+        // the first stage of the per-body scheme comes from the pairwise pass
+        let end = tick(&params, &system).unwrap();
+        for (e, state) in end.iter().zip(&system) {
+            let reference = rk4(params.time_period_sec, state, |s| {
+                grav_rate_func_params(s, &system, &params)
+            })
+            .unwrap();
+            assert_eq!(e.get_position(), reference.get_position());
+            assert_eq!(e.get_velocity(), reference.get_velocity());
+        }
+
+        // pairs failing together still fail
+        let coincident = vec![
+            MotionState::new(0, PRECISION as u64, 0, 0, 0, 0),
+            MotionState::new(1, PRECISION as u64, 0, 0, 0, 0),
+        ];
+        assert_eq!(
+            grav_rate_func_system_params(&coincident, &PhysicsParams::default()).unwrap_err(),
+            SimError::ZeroDistance
         );
     }
 
+    fn assert_same_system(system: &[MotionState], expected: &[MotionState]) {
+        assert_eq!(system.len(), expected.len());
+        for (s, e) in system.iter().zip(expected) {
+            assert_eq!((s.get_x(), s.get_y()), (e.get_x(), e.get_y()));
+            assert_eq!(
+                (s.get_vel_x(), s.get_vel_y()),
+                (e.get_vel_x(), e.get_vel_y())
+            );
+        }
+    }
+
     #[test]
-    fn test_double_orbit() {
-        draw_chart(
-            "./test_plots/double_orbit.png",
-            get_orbit_data(double_orbit(), 4000),
+    fn test_trajectory() {
+        let params = params(Scheme::CoupledRk4, PRECISION / 1000);
+
+        // samples every stride ticks, and at the last tick
+        let mut trajectory =
+            Trajectory::new(params, 1000, simple_orbit_at_8_decimals().system).with_stride(300);
+        let mut ticks = Vec::new();
+        let mut buffers = Vec::new();
+        while let Some(sample) = trajectory.next_sample() {
+            let (tick, system) = sample.unwrap();
+            let expected = tick_many(&params, tick, &simple_orbit_at_8_decimals().system).unwrap();
+            assert_same_system(system, &expected);
+            ticks.push(tick);
+            if !buffers.contains(&system.as_ptr()) {
+                buffers.push(system.as_ptr());
+            }
+        }
+        assert_eq!(ticks, [0, 300, 600, 900, 1000]);
+        // every step is written into one of the same two systems
+        assert!(buffers.len() <= 2);
+        assert_same_system(
+            &trajectory.into_system(),
+            &tick_many(&params, 1000, &simple_orbit_at_8_decimals().system).unwrap(),
+        );
+
+        // ends at the first tick at which the stop condition holds, sampled or not
+        let mut trajectory = Trajectory::new(params, 10000, simple_orbit_at_8_decimals().system)
+            .with_stride(1000)
+            .with_stop(|_, system: &[MotionState]| system[1].get_y() < 0);
+        let mut last = None;
+        while let Some(sample) = trajectory.next_sample() {
+            let (tick, system) = sample.unwrap();
+            last = Some((tick, system[1].get_y()));
+        }
+        let (tick, y) = last.unwrap();
+        assert!(y < 0);
+        assert!(tick % 1000 != 0);
+        let before = tick_many(&params, tick - 1, &simple_orbit_at_8_decimals().system).unwrap();
+        assert!(before[1].get_y() >= 0);
+
+        // a failed step ends the trajectory
+        let coincident = vec![
+            MotionState::new(0, PRECISION as u64, 0, 0, 0, 0),
+            MotionState::new(1, PRECISION as u64, 0, 0, 0, 0),
+        ];
+        let mut trajectory = Trajectory::new(params, 10, coincident);
+        assert!(trajectory.next_sample().unwrap().is_ok());
+        assert_eq!(
+            trajectory.next_sample().unwrap().unwrap_err(),
+            SimError::ZeroDistance
         );
+        assert!(trajectory.next_sample().is_none());
     }
 
     #[test]
-    fn test_our_separate_ways() {
-        draw_chart(
-            "./test_plots/our_separate_ways.png",
-            get_orbit_data(our_separate_ways(), 4000),
+    fn test_checkpoint() {
+        let params = PhysicsParams {
+            softening: PRECISION / 100,
+            ..params(Scheme::Yoshida4, PRECISION / 1000)
+        };
+
+        // a run stopped, encoded, decoded and resumed matches one that never stopped
+        let mut trajectory = Trajectory::new(params, 400, simple_orbit_at_8_decimals().system);
+        while trajectory.next_sample().is_some() {}
+        let bytes = trajectory.checkpoint().encode().unwrap();
+        let checkpoint = Checkpoint::<MotionState>::decode(&bytes).unwrap();
+        assert_eq!((checkpoint.params, checkpoint.tick), (params, 400));
+        let resumed = checkpoint.resume(600).unwrap();
+        assert_eq!(resumed.tick, 1000);
+        assert_same_system(
+            &resumed.system,
+            &tick_many(&params, 1000, &simple_orbit_at_8_decimals().system).unwrap(),
+        );
+
+        // the layout is fixed and little-endian
+        assert_eq!(&bytes[..6], b"RKFC\x01\x02");
+        assert_eq!(&bytes[6..14], &PRECISION.to_le_bytes());
+        assert_eq!(bytes[38], Scheme::Yoshida4.id());
+        assert_eq!(&bytes[39..44], &[1, 0, 0, 0, 0]);
+        assert_eq!(&bytes[44..48], &400u32.to_le_bytes());
+        assert_eq!(&bytes[48..52], &2u32.to_le_bytes());
+        assert_eq!(bytes.len(), 52 + 2 * (21 + 4 * 8));
+
+        // bodies come back whole, radii, kinds and the third axis included
+        let mut system = cluster(5, false);
+        system[0] = system[0].clone().with_radius(PRECISION as u64);
+        system[1] = system[1].with_kind(BodyKind::Anchor);
+        system[2] = system[2].with_kind(BodyKind::Tracer);
+        let checkpoint = Checkpoint {
+            params,
+            force: ForceLaw::Gravity,
+            tick: 7,
+            system: system.clone(),
+        };
+        let decoded = Checkpoint::<MotionState3D>::decode(&checkpoint.encode().unwrap()).unwrap();
+        for (d, s) in decoded.system.iter().zip(&system) {
+            assert_eq!(
+                (d.get_id(), d.get_mass(), d.get_radius(), d.get_kind()),
+                (s.get_id(), s.get_mass(), s.get_radius(), s.get_kind())
+            );
+            assert_eq!(d.get_position(), s.get_position());
+            assert_eq!(d.get_velocity(), s.get_velocity());
+        }
+
+        // anything else is refused
+        let decode = |bytes: &[u8]| Checkpoint::<MotionState>::decode(bytes).unwrap_err();
+        assert_eq!(
+            decode(&bytes[..bytes.len() - 1]),
+            CheckpointError::Truncated
+        );
+        assert_eq!(
+            decode(&[&bytes[..], &[0]].concat()),
+            CheckpointError::TrailingBytes
+        );
+        assert_eq!(decode(b"{}"), CheckpointError::Truncated);
+        assert_eq!(
+            decode(&[b"RKFD", &bytes[4..]].concat()),
+            CheckpointError::NotACheckpoint
+        );
+        let mut changed = bytes.clone();
+        changed[4] = CHECKPOINT_VERSION + 1;
+        assert_eq!(
+            decode(&changed),
+            CheckpointError::UnsupportedVersion(CHECKPOINT_VERSION + 1)
+        );
+        changed = bytes.clone();
+        changed[38] = 9;
+        assert_eq!(decode(&changed), CheckpointError::UnknownScheme(9));
+        changed = bytes.clone();
+        changed[43] = 9;
+        assert_eq!(decode(&changed), CheckpointError::UnknownForce);
+        changed = bytes.clone();
+        changed[52 + 20] = 3;
+        assert_eq!(decode(&changed), CheckpointError::UnknownKind(3));
+        assert_eq!(
+            Checkpoint::<MotionState3D>::decode(&bytes).unwrap_err(),
+            CheckpointError::WrongDimensions(2)
+        );
+        assert_eq!(
+            Checkpoint::<MotionState<FixedPoint<1000000>>>::decode(&bytes).unwrap_err(),
+            CheckpointError::WrongScale(PRECISION)
+        );
+
+        // a run under another force law resumes under it
+        let coulomb = Coulomb {
+            coulomb_k: PRECISION / 2,
+            charges: vec![PRECISION, -PRECISION],
+        };
+        let mut trajectory = Trajectory::new(params, 400, simple_orbit_at_8_decimals().system)
+            .with_force(coulomb.clone());
+        while trajectory.next_sample().is_some() {}
+        let bytes = trajectory.checkpoint().encode().unwrap();
+        let checkpoint = Checkpoint::<MotionState>::decode(&bytes).unwrap();
+        assert_eq!(checkpoint.force, ForceLaw::Coulomb(coulomb.clone()));
+        let resumed = checkpoint.resume(600).unwrap();
+        let straight = tick_many_force(
+            &params,
+            &coulomb,
+            1000,
+            &simple_orbit_at_8_decimals().system,
+        )
+        .unwrap();
+        assert_same_system(&resumed.system, &straight);
+        assert!(
+            max_offset(
+                &straight,
+                &tick_many(&params, 1000, &simple_orbit_at_8_decimals().system).unwrap()
+            ) > 0
         );
     }
 
+    /// The acceleration of body 0 of `system` under `force`, along x.
+    fn pull<F: ForceModel>(force: &F, system: &Vec<MotionState>) -> i64 {
+        force
+            .rate_func(&system[0], system, &PhysicsParams::default())
+            .unwrap()
+            .get_vel_x()
+    }
+
     #[test]
-    fn test_fig_eight() {
-        draw_chart(
-            "./test_plots/fig_eight.png",
-            get_orbit_data(init_stable_figure_eight(), 4000),
+    fn test_force_models() {
+        let params = params(Scheme::CoupledRk4, PRECISION / 1000);
+        let expected = tick_many(&params, 500, &figure_of_eight_spread().system).unwrap();
+
+        // gravity is what `tick` steps with, and adding no drag changes nothing
+        for force in [
+            ForceLaw::Gravity,
+            ForceLaw::Sum(vec![
+                ForceLaw::Gravity,
+                ForceLaw::Drag(Drag { coefficient: 0 }),
+            ]),
+        ] {
+            assert_same_system(
+                &tick_many_force(&params, &force, 500, &figure_of_eight_spread().system).unwrap(),
+                &expected,
+            );
+        }
+        let pair = (Gravity, Drag { coefficient: 0 });
+        assert_same_system(
+            &tick_many_force(&params, &pair, 500, &figure_of_eight_spread().system).unwrap(),
+            &expected,
+        );
+
+        // two bodies two units apart, at rest
+        let mass = PRECISION as u64;
+        let apart = vec![
+            MotionState::new(0, mass, -PRECISION, 0, 0, 0),
+            MotionState::new(1, mass, PRECISION, 0, 0, 0),
+        ];
+        assert_eq!(pull(&Gravity, &apart), PRECISION / 4);
+        assert_eq!(pull(&PowerLaw { exponent: 2 }, &apart), PRECISION / 4);
+        assert_eq!(pull(&PowerLaw { exponent: 3 }, &apart), PRECISION / 8);
+        assert_eq!(pull(&PowerLaw { exponent: 0 }, &apart), PRECISION);
+
+        // like charges repel, unlike ones attract, and neutral bodies feel nothing
+        let coulomb = |charges: Vec<i64>| Coulomb {
+            coulomb_k: 2 * PRECISION,
+            charges,
+        };
+        assert_eq!(
+            pull(&coulomb(vec![PRECISION, PRECISION]), &apart),
+            -PRECISION / 2
+        );
+        assert_eq!(
+            pull(&coulomb(vec![PRECISION, -PRECISION]), &apart),
+            PRECISION / 2
+        );
+        assert_eq!(pull(&coulomb(vec![0, PRECISION]), &apart), 0);
+        assert_eq!(pull(&coulomb(vec![PRECISION]), &apart), 0);
+
+        // a spring pulls when stretched, pushes when compressed, and goes with its body
+        let spring = |rest_length: i64| Springs {
+            springs: vec![Spring {
+                a: 1,
+                b: 0,
+                stiffness: 3 * PRECISION,
+                rest_length,
+            }],
+        };
+        assert_eq!(pull(&spring(PRECISION), &apart), 3 * PRECISION);
+        assert_eq!(pull(&spring(3 * PRECISION), &apart), -3 * PRECISION);
+        assert_eq!(pull(&spring(PRECISION), &apart[..1].to_vec()), 0);
+
+        // drag slows a free body by a factor of e over a unit of time
+        let free = vec![MotionState::new(0, mass, 0, 0, PRECISION, 0)];
+        let drag = Drag {
+            coefficient: PRECISION,
+        };
+        let slowed = tick_many_force(&params, &drag, 1000, &free).unwrap();
+        let expected = (PRECISION_FLOAT * (-1.0f64).exp()) as i64;
+        assert!((slowed[0].get_vel_x() - expected).abs() < 1000);
+
+        // a trajectory under a force matches stepping with it
+        let mut trajectory =
+            Trajectory::new(params, 100, figure_of_eight_spread().system).with_force(drag);
+        while trajectory.next_sample().is_some() {}
+        assert_same_system(
+            &trajectory.into_system(),
+            &tick_many_force(&params, &drag, 100, &figure_of_eight_spread().system).unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_force_law_encoding() {
+        let laws = [
+            ForceLaw::Gravity,
+            ForceLaw::PowerLaw(PowerLaw { exponent: 3 }),
+            ForceLaw::Coulomb(Coulomb {
+                coulomb_k: PRECISION,
+                charges: vec![-1, 0, 1],
+            }),
+            ForceLaw::Springs(Springs {
+                springs: vec![Spring {
+                    a: 0,
+                    b: 2,
+                    stiffness: PRECISION,
+                    rest_length: -1,
+                }],
+            }),
+            ForceLaw::Sum(vec![
+                ForceLaw::Gravity,
+                ForceLaw::Drag(Drag {
+                    coefficient: PRECISION / 10,
+                }),
+            ]),
+        ];
+        for law in laws {
+            assert_eq!(ForceLaw::decode(&law.encode()), Some(law.clone()));
+            let mut longer = law.encode();
+            longer.push(0);
+            assert_eq!(ForceLaw::decode(&longer), None);
+        }
+
+        // no bytes are gravity, as for tokens recording no law
+        assert_eq!(ForceLaw::decode(&[]), Some(ForceLaw::Gravity));
+        assert_eq!(
+            ForceLaw::decode(&[1, 3]),
+            Some(ForceLaw::PowerLaw(PowerLaw { exponent: 3 }))
+        );
+        assert_eq!(ForceLaw::decode(&[6]), None);
+        // sums do not nest
+        assert_eq!(ForceLaw::decode(&[5, 1, 0, 0, 0, 5, 0, 0, 0, 0]), None);
+        assert_eq!(
+            ForceLaw::decode(&[5, 1, 0, 0, 0, 0]),
+            Some(ForceLaw::Sum(vec![ForceLaw::Gravity]))
+        );
+    }
+
+    /// A star anchored at the origin with a ring of `n` tracers of no mass about it.
+    fn debris_ring(n: u32) -> Vec<MotionState> {
+        let mut system =
+            vec![MotionState::new(0, PRECISION as u64, 0, 0, 0, 0).with_kind(BodyKind::Anchor)];
+        for i in 1..=n {
+            let angle = 2.0 * std::f64::consts::PI * i as f64 / n as f64;
+            let (sin, cos) = (
+                (angle.sin() * PRECISION_FLOAT) as i64,
+                (angle.cos() * PRECISION_FLOAT) as i64,
+            );
+            system.push(MotionState::new(i, 0, cos, sin, -sin, cos).with_kind(BodyKind::Tracer));
+        }
+        system
+    }
+
+    #[test]
+    fn test_body_kinds() {
+        let time_period_sec = PRECISION / 1000;
+        for scheme in [
+            Scheme::PerBodyRk4,
+            Scheme::CoupledRk4,
+            Scheme::Leapfrog,
+            Scheme::Yoshida4,
+        ] {
+            let params = params(scheme, time_period_sec);
+
+            // an anchor keeps its place and velocity while its tracers circle it
+            let ring = tick_many(&params, 1000, &debris_ring(8)).unwrap();
+            assert_same_system(&ring[..1], &debris_ring(8)[..1]);
+            for tracer in &ring[1..] {
+                let r = (tracer.get_x() as f64).hypot(tracer.get_y() as f64);
+                assert!((r - PRECISION_FLOAT).abs() < PRECISION_FLOAT / 1000.0);
+                assert_eq!(tracer.get_kind(), BodyKind::Tracer);
+            }
+
+            // tracers pull nothing, whatever their mass, and the free bodies move as if
+            // they were not there
+            let mut dusty = figure_of_eight_spread().system;
+            for i in 0..20 {
+                let x = (i - 10) * PRECISION / 5;
+                dusty.push(
+                    MotionState::new(3 + i as u32, PRECISION as u64, x, PRECISION, 0, 0)
+                        .with_kind(BodyKind::Tracer),
+                );
+            }
+            let dusty = tick_many(&params, 200, &dusty).unwrap();
+            assert_same_system(
+                &dusty[..3],
+                &tick_many(&params, 200, &figure_of_eight_spread().system).unwrap(),
+            );
+
+            // and are pulled as free bodies of no mass are
+            let massless: Vec<MotionState> = debris_ring(8)
+                .iter()
+                .map(|s| s.with_kind(BodyKind::Free))
+                .collect();
+            let free = tick_many(&params, 50, &massless).unwrap();
+            let traced = tick_many(&params, 50, &debris_ring(8)).unwrap();
+            assert_same_system(&traced[1..], &free[1..]);
+
+            // a tree walked for every tracer agrees with the direct sum
+            let tree = tick_many_barnes_hut(&params, &BarnesHut { theta: 0 }, 50, &debris_ring(8));
+            assert_same_system(&tree.unwrap(), &traced);
+        }
+
+        // an anchor is pinned under any force model
+        let mut pinned = figure_of_eight_spread().system;
+        pinned[1] = pinned[1].with_kind(BodyKind::Anchor);
+        let force = (
+            Gravity,
+            Drag {
+                coefficient: PRECISION,
+            },
+        );
+        let params = params(Scheme::CoupledRk4, time_period_sec);
+        let pinned_after = tick_many_force(&params, &force, 100, &pinned).unwrap();
+        assert_same_system(&pinned_after[1..2], &pinned[1..2]);
+
+        // charges and springs have no inertia to act on in a tracer of no mass, which is
+        // left to gravity
+        let charged = (
+            Gravity,
+            (
+                Coulomb {
+                    coulomb_k: PRECISION,
+                    charges: vec![PRECISION; 9],
+                },
+                Springs {
+                    springs: vec![Spring {
+                        a: 0,
+                        b: 1,
+                        stiffness: PRECISION,
+                        rest_length: 0,
+                    }],
+                },
+            ),
+        );
+        assert_same_system(
+            &tick_many_force(&params, &charged, 100, &debris_ring(8)).unwrap(),
+            &tick_many(&params, 100, &debris_ring(8)).unwrap(),
+        );
+
+        // whatever hits an anchor is taken in where the anchor is, and tracers merge
+        // into a tracer
+        let radius = PRECISION as u64 / 10;
+        let system = vec![
+            MotionState::new(0, PRECISION as u64, 0, 0, PRECISION, 0).with_radius(radius),
+            MotionState::new(1, PRECISION as u64, 0, 0, 0, 0)
+                .with_radius(radius)
+                .with_kind(BodyKind::Anchor),
+            MotionState::new(2, 0, 0, PRECISION, 0, 0).with_kind(BodyKind::Tracer),
+            MotionState::new(3, 0, PRECISION / 2, PRECISION, 0, 0)
+                .with_radius(radius * 5)
+                .with_kind(BodyKind::Tracer),
+        ];
+        let run = tick_many_merging(&params, 0, &system).unwrap();
+        assert_eq!(run.system.len(), 2);
+        assert_eq!(run.system[0].get_kind(), BodyKind::Anchor);
+        assert_same_system(&run.system[..1], &system[1..2]);
+        assert_eq!(run.system[0].get_mass(), 2 * PRECISION as u64);
+        assert_eq!(run.system[1].get_kind(), BodyKind::Tracer);
+        assert_eq!(run.system[1].get_x(), PRECISION / 4);
+
+        // kinds are read by id, bodies of old recordings being free
+        let json = r#"{"id":0,"mass":1,"kind":2,"x":0,"y":0,"vel_x":0,"vel_y":0}"#;
+        let tracer: MotionState = serde_json::from_str(json).unwrap();
+        assert_eq!(tracer.get_kind(), BodyKind::Tracer);
+        let old: MotionState = serde_json::from_str(&json.replace(r#""kind":2,"#, "")).unwrap();
+        assert_eq!(old.get_kind(), BodyKind::Free);
+        assert!(serde_json::from_str::<MotionState>(&json.replace(":2,", ":3,")).is_err());
+    }
+
+    /// The largest distance between a body of `system` and where it was in `start`.
+    fn max_offset(system: &[MotionState], start: &[MotionState]) -> i64 {
+        system
+            .iter()
+            .zip(start)
+            .map(|(s, e)| ((s.get_x() - e.get_x()) as f64).hypot((s.get_y() - e.get_y()) as f64))
+            .fold(0.0, f64::max) as i64
+    }
+
+    /// The arrays `preset` is minted from, as the presets example prints them.
+    fn bodies_of(preset: &Preset) -> Bodies {
+        let system = &preset.system;
+        (
+            system.iter().map(|s| s.get_mass()).collect(),
+            system.iter().map(|s| s.get_x()).collect(),
+            system.iter().map(|s| s.get_y()).collect(),
+            system.iter().map(|s| s.get_vel_x()).collect(),
+            system.iter().map(|s| s.get_vel_y()).collect(),
+        )
+    }
+
+    /// `preset` minted by the contract with the options mint.sh has by default.
+    fn mint_preset(preset: &Preset) -> Result<Minted, MintError> {
+        let system = &preset.system;
+        let kind = if system.iter().all(|s| s.get_kind() == BodyKind::Free) {
+            Vec::new()
+        } else {
+            system.iter().map(|s| s.get_kind().id()).collect()
+        };
+        let options = (
+            8,
+            Scheme::PerBodyRk4.id(),
+            PRECISION,
+            PRECISION / 1000,
+            0,
+            Vec::new(),
+            kind,
+            0,
+            false,
+            false,
+            false,
+        );
+        minted(&options, bodies_of(preset), preset.ticks)
+    }
+
+    #[test]
+    fn test_presets() {
+        let all = presets();
+        for (i, preset) in all.iter().enumerate() {
+            assert!(all[..i].iter().all(|other| other.name != preset.name));
+            let found = rk_fall::presets::preset(preset.name).unwrap();
+            assert_eq!(found.name, preset.name);
+            assert!(preset
+                .system
+                .iter()
+                .zip(0..)
+                .all(|(s, id)| s.get_id() == id));
+
+            // each can be minted for its ticks
+            tick_many(&PhysicsParams::default(), preset.ticks, &preset.system).unwrap();
+        }
+        assert!(rk_fall::presets::preset("no such preset").is_none());
+
+        // and minted by the contract with the defaults of mint.sh, equal masses and all
+        let mut token_ids = Vec::new();
+        for preset in &all {
+            let minted = mint_preset(preset).unwrap();
+            let last = tick_many(&PhysicsParams::default(), preset.ticks, &preset.system).unwrap();
+            assert_eq!(
+                minted.last[0],
+                last.iter().map(|s| s.get_x()).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                minted.last[3],
+                last.iter().map(|s| s.get_vel_y()).collect::<Vec<_>>()
+            );
+            assert!(!token_ids.contains(&minted.token_id));
+            token_ids.push(minted.token_id);
+        }
+
+        // tokens of distinct masses keep their original token ids, while equal masses
+        // that moved otherwise then take the id of their options
+        let original = |preset: &Preset| {
+            let (mass, x, y, vel_x, vel_y) = bodies_of(preset);
+            let data = <(
+                Array<Uint<64>>,
+                Array<Int<64>>,
+                Array<Int<64>>,
+                Array<Int<64>>,
+                Array<Int<64>>,
+                Uint<32>,
+            )>::encode(&(mass, x, y, vel_x, vel_y, preset.ticks));
+            let token_id: U256 = keccak256(data).into();
+            token_id
+        };
+        let separate = rk_fall::presets::preset("our separate ways").unwrap();
+        assert_eq!(
+            mint_preset(&separate).unwrap().token_id,
+            original(&separate)
+        );
+        let lagrange = rk_fall::presets::preset("lagrange equilateral").unwrap();
+        assert_ne!(
+            mint_preset(&lagrange).unwrap().token_id,
+            original(&lagrange)
+        );
+
+        // the periodic orbits come back round after their ticks
+        let params = params(Scheme::Yoshida4, PRECISION / 1000);
+        for name in ["lagrange equilateral", "broucke a1", "broucke a2"] {
+            let Preset { ticks, system, .. } = rk_fall::presets::preset(name).unwrap();
+            let after = tick_many(&params, ticks, &system).unwrap();
+            assert!(max_offset(&after, &system) < PRECISION / 1000);
+        }
+    }
+
+    #[test]
+    fn test_events() {
+        let params = params(Scheme::CoupledRk4, PRECISION / 1000);
+
+        // looking out for nothing finds nothing and leaves the run as it was
+        let run = tick_many_events(
+            &params,
+            &Gravity,
+            &EventDetector::default(),
+            500,
+            &figure_of_eight_spread().system,
+        )
+        .unwrap();
+        assert!(run.events.is_empty());
+        assert_same_system(
+            &run.system,
+            &tick_many(&params, 500, &figure_of_eight_spread().system).unwrap(),
+        );
+
+        // two light bodies passing a fifth of a unit apart come within half a unit once
+        let light = PRECISION as u64 / 1000;
+        let passing = vec![
+            MotionState::new(0, light, -PRECISION, 0, PRECISION, 0),
+            MotionState::new(1, light, PRECISION, PRECISION / 5, -PRECISION, 0),
+        ];
+        let close = EventDetector {
+            close_approach: PRECISION / 2,
+            ..EventDetector::default()
+        };
+        let run = tick_many_events(&params, &Gravity, &close, 2000, &passing).unwrap();
+        assert_eq!(run.events.len(), 1);
+        let event = run.events[0];
+        assert_eq!(event.kind, EventKind::CloseApproach);
+        assert_eq!((event.id, event.other_id), (0, 1));
+        assert!((765..780).contains(&event.tick));
+
+        // an eccentric orbit, starting at apoapsis, passes periapsis and apoapsis by turns
+        let mut eccentric = simple_orbit_at_8_decimals().system;
+        eccentric[1] = MotionState::new(1, light / 10, 0, PRECISION, PRECISION / 2, 0);
+        let apsides = EventDetector {
+            apsides: true,
+            ..EventDetector::default()
+        };
+        let run = tick_many_events(&params, &Gravity, &apsides, 6000, &eccentric).unwrap();
+        let passes: Vec<&Event> = run.events.iter().filter(|e| e.id == 1).collect();
+        let kinds: Vec<EventKind> = passes.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                EventKind::Periapsis,
+                EventKind::Apoapsis,
+                EventKind::Periapsis,
+                EventKind::Apoapsis
+            ]
+        );
+        // half a period of 2 pi (4/7)^(3/2) apart
+        for pair in passes.windows(2) {
+            assert!((pair[1].tick - pair[0].tick).abs_diff(1357) < 20);
+        }
+        // the star sees the same orbit from the other end
+        for (star, planet) in run.events.iter().filter(|e| e.id == 0).zip(&passes) {
+            assert_eq!((star.tick, star.kind), (planet.tick, planet.kind));
+        }
+
+        // an anchor has no orbit, and a tracer orbits it as a massless body would a star
+        let anchored = vec![
+            eccentric[0].with_kind(BodyKind::Anchor),
+            eccentric[1].with_kind(BodyKind::Tracer),
+        ];
+        let massless = vec![eccentric[0].clone(), eccentric[1].with_mass(0, 0)];
+        let run = tick_many_events(&params, &Gravity, &apsides, 3000, &anchored).unwrap();
+        assert_eq!(run.events.len(), 2);
+        assert!(run.events.iter().all(|e| e.id == 1));
+        assert_eq!(
+            run.events,
+            tick_many_events(&params, &Gravity, &apsides, 3000, &massless)
+                .unwrap()
+                .events
+        );
+
+        // a pair at rest is bound until like charges push it apart
+        let mass = PRECISION as u64;
+        let apart = vec![
+            MotionState::new(0, mass, -PRECISION, 0, 0, 0),
+            MotionState::new(1, mass, PRECISION, 0, 0, 0),
+        ];
+        let force = (
+            Gravity,
+            Coulomb {
+                coulomb_k: 5 * PRECISION,
+                charges: vec![PRECISION, PRECISION],
+            },
+        );
+        let escapes = EventDetector {
+            escapes: true,
+            ..EventDetector::default()
+        };
+        let run = tick_many_events(&params, &force, &escapes, 2000, &apart).unwrap();
+        assert_eq!(run.events.len(), 2);
+        assert_eq!(run.events[0].tick, run.events[1].tick);
+        for (event, id) in run.events.iter().zip(0..) {
+            assert_eq!(event.kind, EventKind::Escape);
+            assert_eq!((event.id, event.other_id), (id, id));
+        }
+        // while a pair held by gravity alone stays bound
+        let run = tick_many_events(&params, &Gravity, &escapes, 1000, &apart).unwrap();
+        assert!(run.events.is_empty());
+
+        // events are recorded with their kinds by id
+        let json = r#"{"tick":7,"kind":1,"id":2,"other_id":2}"#;
+        let event: Event = serde_json::from_str(json).unwrap();
+        assert_eq!(event.kind, EventKind::Escape);
+        assert_eq!(serde_json::to_string(&event).unwrap(), json);
+        assert!(serde_json::from_str::<Event>(&json.replace(":1,", ":4,")).is_err());
+    }
+
+    fn fixed(x: f64) -> Fixed {
+        Fixed::from_raw((x * PRECISION_FLOAT).round() as i64)
+    }
+
+    #[test]
+    fn test_trig() {
+        assert_eq!(Fixed::PI.raw(), 314159265);
+        assert_eq!(FixedPoint::<1000000000000>::PI.raw(), 3141592653590);
+
+        // within a unit of the rounded value, however many turns away from zero
+        for i in -200..=200 {
+            let angle = i as f64 * 0.0537;
+            let (sin, cos) = fixed(angle).checked_sin_cos().unwrap();
+            let angle = fixed(angle).raw() as f64 / PRECISION_FLOAT;
+            assert!((sin.raw() as f64 - angle.sin() * PRECISION_FLOAT).abs() <= 1.0);
+            assert!((cos.raw() as f64 - angle.cos() * PRECISION_FLOAT).abs() <= 1.0);
+        }
+        let (sin, cos) = Fixed::ZERO.checked_sin_cos().unwrap();
+        assert_eq!((sin, cos), (Fixed::ZERO, Fixed::ONE));
+        let fine = FixedPoint::<1000000000000>::from_raw(1234567890123);
+        let fine_sin = fine.checked_sin().unwrap().raw() as f64;
+        assert!((fine_sin - 1.234567890123f64.sin() * 1e12).abs() <= 2.0);
+
+        for x in -6..=6 {
+            for y in -6..=6 {
+                let (x, y) = (x as f64 * 0.7, y as f64 * 0.3);
+                let angle = fixed(y).checked_atan2(fixed(x)).unwrap();
+                if x == 0.0 && y == 0.0 {
+                    assert_eq!(angle, Fixed::ZERO);
+                } else {
+                    let expected = y.atan2(x) * PRECISION_FLOAT;
+                    assert!((angle.raw() as f64 - expected).abs() <= 1.0);
+                }
+            }
+        }
+        // the negative x axis is at π rather than -π, and scale makes no difference
+        assert_eq!(
+            Fixed::ZERO
+                .checked_atan2(Fixed::from_raw(-PRECISION))
+                .unwrap(),
+            Fixed::PI
+        );
+        assert_eq!(
+            Fixed::from_raw(3).checked_atan2(Fixed::from_raw(3)),
+            Fixed::MAX.checked_atan2(Fixed::MAX)
+        );
+    }
+
+    /// How far `angle` is from `expected`, in units of the last place and whole turns
+    /// apart being no distance.
+    fn angle_error(angle: Fixed, expected: f64) -> f64 {
+        let tau = 2.0 * std::f64::consts::PI * PRECISION_FLOAT;
+        let error = (angle.raw() as f64 - expected * PRECISION_FLOAT).rem_euclid(tau);
+        error.min(tau - error)
+    }
+
+    #[test]
+    fn test_kepler() {
+        let grav_g = PRECISION;
+        let star = MotionState::new(0, PRECISION as u64, 0, 0, 0, 0);
+        let circular = OrbitalElements {
+            semi_major_axis: Fixed::ONE,
+            eccentricity: Fixed::ZERO,
+            argument_of_periapsis: Fixed::ZERO,
+            anomaly: Anomaly::True(Fixed::ZERO),
+            retrograde: false,
+        };
+
+        // a circular orbit of unit radius about a unit mass moves at unit speed
+        let planet = circular.to_state(grav_g, &star, 1, 0).unwrap();
+        assert_eq!(
+            (planet.get_x(), planet.get_y(), planet.get_vel_x()),
+            (PRECISION, 0, 0)
+        );
+        assert!((planet.get_vel_y() - PRECISION).abs() <= 1);
+
+        // the planet of `single_orbit` runs round its star clockwise
+        let system = simple_orbit_at_8_decimals().system;
+        let elements = OrbitalElements::from_state(grav_g, &system[0], &system[1]).unwrap();
+        assert!(elements.retrograde);
+        assert!(elements.eccentricity.raw() < 10000);
+        assert!((elements.semi_major_axis.raw() - PRECISION).abs() < 20000);
+
+        // elements survive the round trip through a state
+        for e in [0.0, 0.1, 0.5, 0.9] {
+            for omega in [0.0, 1.0, -2.5] {
+                for nu in [0.0, 0.7, 3.0, -1.5] {
+                    for retrograde in [false, true] {
+                        let elements = OrbitalElements {
+                            semi_major_axis: fixed(1.5),
+                            eccentricity: fixed(e),
+                            argument_of_periapsis: fixed(omega),
+                            anomaly: Anomaly::True(fixed(nu)),
+                            retrograde,
+                        };
+                        let state = elements.to_state(grav_g, &star, 1, 0).unwrap();
+                        let back = OrbitalElements::from_state(grav_g, &star, &state).unwrap();
+                        assert_eq!(back.retrograde, retrograde);
+                        // the energy is least well resolved when close in on an eccentric orbit
+                        let a_error = back.semi_major_axis.raw() - fixed(1.5).raw();
+                        assert!(a_error.abs() < PRECISION / 50000);
+                        assert!((back.eccentricity.raw() - fixed(e).raw()).abs() < 100);
+                        if e > 0.0 {
+                            let omega_error = angle_error(back.argument_of_periapsis, omega);
+                            assert!(omega_error < 1000.0);
+                        }
+                        let Anomaly::True(back_nu) = back.anomaly else {
+                            panic!("from_state gives the true anomaly");
+                        };
+                        // where the body is is well defined even when periapsis is not
+                        let sense = if retrograde { -1 } else { 1 };
+                        let along = back_nu.checked_mul_int(sense).unwrap();
+                        let along = along.checked_add(back.argument_of_periapsis).unwrap();
+                        assert!(angle_error(along, omega + sense as f64 * nu) < 1000.0);
+
+                        // the same place named by its mean anomaly
+                        let mean = OrbitalElements {
+                            anomaly: Anomaly::Mean(elements.mean_anomaly().unwrap()),
+                            ..elements
+                        };
+                        let nu_error = mean.true_anomaly().unwrap().raw() - fixed(nu).raw();
+                        assert!(nu_error.abs() < 100);
+                        let moved = mean.to_state(grav_g, &star, 1, 0).unwrap();
+                        for (value, expected) in [
+                            (moved.get_x(), state.get_x()),
+                            (moved.get_y(), state.get_y()),
+                            (moved.get_vel_x(), state.get_vel_x()),
+                            (moved.get_vel_y(), state.get_vel_y()),
+                        ] {
+                            assert!((value - expected).abs() < 100);
+                        }
+                    }
+                }
+            }
+        }
+
+        // the mean anomaly grows steadily, by n = √(μ / a³) a second
+        let eccentric = OrbitalElements {
+            semi_major_axis: fixed(0.8),
+            eccentricity: fixed(0.6),
+            argument_of_periapsis: fixed(0.3),
+            anomaly: Anomaly::Mean(fixed(-2.0)),
+            retrograde: true,
+        };
+        let mut builder = SystemBuilder::new(grav_g, PRECISION as u64);
+        builder.orbiting(0, 0, &eccentric).unwrap();
+        let after = tick_many(
+            &params(Scheme::Yoshida4, PRECISION / 1000),
+            2000,
+            &builder.build(),
+        )
+        .unwrap();
+        let elements = OrbitalElements::from_state(grav_g, &after[0], &after[1]).unwrap();
+        let swept = elements.mean_anomaly().unwrap().raw() - fixed(-2.0).raw();
+        assert!((swept as f64 - 2.0 / 0.8f64.powf(1.5) * PRECISION_FLOAT).abs() < 20000.0);
+
+        // a moon about a planet about a star
+        let mut builder = SystemBuilder::new(grav_g, PRECISION as u64);
+        let planet = builder
+            .orbiting(0, PRECISION as u64 / 1000, &circular)
+            .unwrap();
+        let moon_orbit = OrbitalElements {
+            semi_major_axis: fixed(0.05),
+            eccentricity: fixed(0.2),
+            argument_of_periapsis: fixed(1.0),
+            anomaly: Anomaly::Mean(fixed(0.5)),
+            retrograde: false,
+        };
+        let moon = builder.orbiting(planet, 0, &moon_orbit).unwrap();
+        assert_eq!((planet, moon), (1, 2));
+        assert_eq!(
+            builder.orbiting(3, 0, &circular).unwrap_err(),
+            OrbitError::UnknownPrimary(3)
+        );
+        let system = builder.build();
+        assert_eq!(system.len(), 3);
+        let back = OrbitalElements::from_state(grav_g, &system[1], &system[2]).unwrap();
+        let mean_error = back.mean_anomaly().unwrap().raw() - fixed(0.5).raw();
+        assert!(mean_error.abs() < PRECISION / 50000);
+        assert!((back.semi_major_axis.raw() - fixed(0.05).raw()).abs() < 100);
+
+        // only ellipses are orbits
+        for (a, e) in [(1.0, 1.0), (1.0, -0.1), (0.0, 0.5)] {
+            let elements = OrbitalElements {
+                semi_major_axis: fixed(a),
+                eccentricity: fixed(e),
+                ..circular
+            };
+            assert_eq!(
+                elements.to_state(grav_g, &star, 1, 0).unwrap_err(),
+                OrbitError::Unbound
+            );
+        }
+        let escaping = MotionState::new(1, 0, PRECISION, 0, 0, 3 * PRECISION / 2);
+        assert_eq!(
+            OrbitalElements::from_state(grav_g, &star, &escaping),
+            Err(OrbitError::Unbound)
+        );
+
+        let json = r#"{"semi_major_axis":100000000,"eccentricity":0,"argument_of_periapsis":0,"anomaly":{"True":0},"retrograde":false}"#;
+        assert_eq!(serde_json::to_string(&circular).unwrap(), json);
+    }
+
+    #[test]
+    fn test_fixed() {
+        let big = Fixed::from_raw(i64::MAX / 2);
+
+        // products are exact even where they overflow i64 on the way
+        assert_eq!(big.checked_mul(Fixed::ONE), Ok(big));
+        assert_eq!(big.checked_div(Fixed::ONE), Ok(big));
+        assert_eq!(big.mul_div(big, big), Ok(big));
+        assert_eq!(big.checked_mul_int(4), Err(SimError::Overflow));
+        assert_eq!(big.checked_mul(big), Err(SimError::Overflow));
+        assert_eq!(big.saturating_mul(big), Fixed::MAX);
+        assert_eq!(big.checked_neg().unwrap().saturating_mul(big), Fixed::MIN);
+        assert_eq!(Fixed::MAX.saturating_add(Fixed::ONE), Fixed::MAX);
+        assert_eq!(Fixed::ONE.checked_div(Fixed::ZERO), Err(SimError::Overflow));
+
+        let four = Fixed::ONE.checked_mul_int(4).unwrap();
+        assert_eq!(
+            four.checked_sqrt(),
+            Ok(Fixed::ONE.checked_mul_int(2).unwrap())
+        );
+        assert_eq!(
+            four.checked_neg().unwrap().checked_sqrt(),
+            Err(SimError::NegativeSqrt)
+        );
+    }
+
+    #[test]
+    fn test_roots() {
+        // the floor of the root, at the edges of squares and of the u64 fast path
+        let mut values: Vec<u128> = (0..10000).collect();
+        for bits in 1..128 {
+            values.extend([(1 << bits) - 1, 1 << bits, (1 << bits) + 1]);
+        }
+        values.extend([u64::MAX as u128 + 1, u128::MAX, (u64::MAX as u128).pow(2)]);
+        for x in values {
+            let root = isqrt(x);
+            assert!(root * root <= x, "{}", x);
+            assert!((root + 1).checked_mul(root + 1).is_none_or(|next| next > x));
+        }
+
+        // n / √x rounded down exactly
+        for (n, x) in [
+            (1, 2),
+            (100, 3),
+            (7, 49),
+            (PRECISION as u128, 3),
+            (1 << 60, 5),
+        ] {
+            let q = div_sqrt(n, x).unwrap();
+            assert!(q * q * x <= n * n && (q + 1) * (q + 1) * x > n * n);
+        }
+        assert_eq!(div_sqrt(1, 0), None);
+        assert_eq!(mul_div_sqrt_cubed(3, 9, 9), Some(1));
+
+        let quarter = Fixed::from_raw(PRECISION / 4);
+        let two = Fixed::ONE.checked_mul_int(2).unwrap();
+        assert_eq!(quarter.checked_rsqrt(), Ok(two));
+        assert_eq!(
+            two.checked_mul(two).unwrap().checked_rsqrt(),
+            Ok(Fixed::from_raw(PRECISION / 2))
+        );
+        assert_eq!(Fixed::ZERO.checked_rsqrt(), Err(SimError::Overflow));
+        assert_eq!(
+            two.checked_neg().unwrap().checked_rsqrt(),
+            Err(SimError::NegativeSqrt)
+        );
+    }
+
+    #[test]
+    fn test_inverse_cube() {
+        // working from the squared distance keeps a close pull within a unit in the last
+        // place, where dividing by the rounded distance three times does not
+        let close = cluster(100, false)
+            .iter()
+            .map(|s| s.get_position().map(|x| Fixed::from_raw(x.raw() / 100)))
+            .collect::<Vec<_>>();
+        let grav_m = Fixed::from_raw(PRECISION / 100);
+        let (mut worst, mut worst_divided) = (0, 0);
+        for d in &close {
+            let exact = WideFixed::from_scaled(grav_m.raw() as i128)
+                .unwrap()
+                .mul_div_norm_cubed(
+                    WideFixed::from_scaled(d[0].raw() as i128).unwrap(),
+                    &d.map(|x| WideFixed::from_scaled(x.raw() as i128).unwrap()),
+                )
+                .unwrap()
+                .to_scaled();
+            let error = |pull: Fixed| (pull.raw() as i128 - exact).abs();
+            worst = worst.max(error(grav_m.mul_div_norm_cubed(d[0], d).unwrap()));
+            let r = Fixed::norm(d).unwrap();
+            let divided = grav_m.mul_div(d[0], r).unwrap();
+            worst_divided = worst_divided.max(error(
+                divided.checked_div(r).unwrap().checked_div(r).unwrap(),
+            ));
+        }
+        assert!(worst <= 1);
+        assert!(worst_divided > 1000);
+
+        // no distance is an overflow rather than a panic
+        assert_eq!(
+            Fixed::ONE.mul_div_norm_cubed(Fixed::ONE, &[Fixed::ZERO; 3]),
+            Err(SimError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_sim_errors() {
+        let time_period_sec = (0.001 * PRECISION_FLOAT) as i64;
+
+        let coincident = vec![
+            MotionState::new(0, PRECISION as u64, 0, 0, 0, 0),
+            MotionState::new(1, PRECISION as u64, 0, 0, 0, 0),
+        ];
+        for id in 0..=8 {
+            let scheme = Scheme::from_id(id).unwrap();
+            assert_eq!(
+                tick_many(&params(scheme, time_period_sec), 1, &coincident).unwrap_err(),
+                SimError::ZeroDistance
+            );
+        }
+
+        let runaway = vec![MotionState::new(
+            0,
+            PRECISION as u64,
+            i64::MAX - 1,
+            0,
+            PRECISION,
+            0,
+        )];
+        for id in 0..=8 {
+            let scheme = Scheme::from_id(id).unwrap();
+            assert_eq!(
+                tick_many(&params(scheme, time_period_sec), 1, &runaway).unwrap_err(),
+                SimError::Overflow
+            );
+        }
+    }
+
+    fn run_backend<S: Scalar>(ticks: u32, system: &Vec<MotionState>) -> Vec<MotionState> {
+        let time_period_sec = (0.001 * PRECISION_FLOAT) as i64;
+        let converted: Vec<MotionState<S>> = system.iter().map(|s| s.convert().unwrap()).collect();
+        tick_many(
+            &params(Scheme::CoupledRk4, time_period_sec),
+            ticks,
+            &converted,
+        )
+        .unwrap()
+        .iter()
+        .map(|s| s.convert().unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn test_backends() {
+        let system = simple_orbit_at_8_decimals().system;
+        let time_period_sec = (0.001 * PRECISION_FLOAT) as i64;
+
+        // the default backend is the one tokens are minted with
+        let fixed = run_backend::<Fixed>(2000, &system);
+        let minted =
+            tick_many(&params(Scheme::CoupledRk4, time_period_sec), 2000, &system).unwrap();
+        assert_eq!(fixed[1].get_x(), minted[1].get_x());
+        assert_eq!(fixed[1].get_y(), minted[1].get_y());
+
+        // the higher precision backends agree to well within the rounding of `Fixed`
+        let reference = run_backend::<WideFixed>(2000, &system);
+        for (other, tolerance) in [
+            (fixed, PRECISION / 10000),
+            (run_backend::<I64F64>(2000, &system), 10),
+            (run_backend::<f64>(2000, &system), 10),
+        ] {
+            assert!((orbit_radius(&other) - 1.0).abs() < 0.001);
+            for (s, r) in other.iter().zip(&reference) {
+                assert!((s.get_x() - r.get_x()).abs() < tolerance);
+                assert!((s.get_y() - r.get_y()).abs() < tolerance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_wide_fixed() {
+        let million = WideFixed::from_scaled(1000000 * PRECISION as i128).unwrap();
+
+        // the product overflows i128 on the way but not in the result
+        let trillion = million.checked_mul(million).unwrap();
+        assert_eq!(trillion.raw(), 1000000000000 * WIDE_PRECISION);
+        assert_eq!(trillion.checked_div(million), Ok(million));
+        assert_eq!(
+            trillion.mul_div(trillion, million).unwrap().to_scaled(),
+            1000000000000000000 * PRECISION as i128
         );
+        assert_eq!(trillion.checked_mul(trillion), Err(SimError::Overflow));
+        assert_eq!(
+            million.checked_mul_int(-1).unwrap().checked_mul(million),
+            trillion.checked_mul_int(-1)
+        );
+
+        fn three_four_five<S: Scalar>() {
+            let three = S::from_scaled(3 * PRECISION as i128).unwrap();
+            let four = S::from_scaled(4 * PRECISION as i128).unwrap();
+            assert_eq!(
+                S::norm(&[three, four]).unwrap().to_scaled(),
+                5 * PRECISION as i128
+            );
+        }
+        three_four_five::<Fixed>();
+        three_four_five::<WideFixed>();
+        three_four_five::<I64F64>();
+        three_four_five::<f64>();
+    }
+
+    fn run_scale<const SCALE: i64>(ticks: u32, system: &Vec<MotionState>) -> Vec<MotionState> {
+        let scaled: Vec<MotionState<FixedPoint<SCALE>>> =
+            system.iter().map(|s| s.convert().unwrap()).collect();
+        let params = PhysicsParams {
+            scheme: Scheme::CoupledRk4,
+            ..PhysicsParams::at_scale(SCALE)
+        };
+        tick_many(&params, ticks, &scaled)
+            .unwrap()
+            .iter()
+            .map(|s| s.convert().unwrap())
+            .collect()
+    }
+
+    fn max_error(system: &Vec<MotionState>, reference: &Vec<MotionState>) -> i64 {
+        system
+            .iter()
+            .zip(reference)
+            .map(|(s, r)| {
+                (s.get_x() - r.get_x())
+                    .abs()
+                    .max((s.get_y() - r.get_y()).abs())
+            })
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_scales() {
+        let system = simple_orbit_at_8_decimals().system;
+
+        // going to a finer scale and back is exact, masses included
+        let fine: Vec<MotionState<FixedPoint<10000000000>>> =
+            system.iter().map(|s| s.convert().unwrap()).collect();
+        assert_eq!(fine[1].get_x(), system[1].get_x() * 100);
+        assert_eq!(fine[1].get_mass(), system[1].get_mass() * 100);
+        let back: Vec<MotionState> = fine.iter().map(|s| s.convert().unwrap()).collect();
+        assert_eq!(max_error(&back, &system), 0);
+        assert_eq!(back[1].get_mass(), system[1].get_mass());
+
+        // a step is the same length of time at every scale, so finer scales only round less
+        let reference = run_backend::<WideFixed>(2000, &system);
+        let coarse = max_error(&run_scale::<1000000>(2000, &system), &reference);
+        let default = max_error(&run_scale::<PRECISION>(2000, &system), &reference);
+        let fine = max_error(&run_scale::<10000000000>(2000, &system), &reference);
+        assert_eq!(
+            default,
+            max_error(&run_backend::<Fixed>(2000, &system), &reference)
+        );
+        assert!(fine < default && default < coarse);
+        assert!(coarse < PRECISION / 100);
+    }
+
+    #[test]
+    fn test_centre_of_mass_frame() {
+        let system = our_separate_ways_spread().system;
+        let mass: i128 = system.iter().map(|s| s.get_mass() as i128).sum();
+        let total = total_momentum(&system).unwrap();
+        assert_ne!(total[..2], [0, 0]);
+        let (momentum_x, momentum_y) = momentum(&system).unwrap();
+        assert!((total[0] / PRECISION as i128 - momentum_x).abs() <= 3);
+        assert!((total[1] / PRECISION as i128 - momentum_y).abs() <= 3);
+
+        // the centre ends up at the origin, to the unit, with almost no momentum, the
+        // bodies keeping their masses and their offsets from each other
+        let centred = centre_of_mass_frame(&system).unwrap();
+        assert_eq!(centre_of_mass(&centred).unwrap(), Some([Fixed::ZERO; 3]));
+        assert!(total_momentum(&centred)
+            .unwrap()
+            .iter()
+            .all(|p| p.abs() < mass));
+        assert_ne!(centred[0].get_x(), system[0].get_x());
+        for (s, c) in system.iter().zip(&centred) {
+            assert_eq!((c.get_id(), c.get_mass()), (s.get_id(), s.get_mass()));
+            assert_eq!(
+                (
+                    c.get_x() - centred[0].get_x(),
+                    c.get_vel_y() - centred[0].get_vel_y()
+                ),
+                (
+                    s.get_x() - system[0].get_x(),
+                    s.get_vel_y() - system[0].get_vel_y()
+                )
+            );
+        }
+        assert_same_system(&centre_of_mass_frame(&centred).unwrap(), &centred);
+
+        // a total mass that does not divide the weighted sums leaves less than itself of
+        // them over, so the centre rounds to the origin without quite being there
+        let uneven = vec![
+            MotionState::new(0, 3, 10, 0, 0, 5),
+            MotionState::new(1, 4, 0, 0, 0, 0),
+        ];
+        let centred = centre_of_mass_frame(&uneven).unwrap();
+        assert_eq!(
+            centred.iter().map(|s| s.get_x()).collect::<Vec<_>>(),
+            [6, -4]
+        );
+        assert_eq!(
+            centred
+                .iter()
+                .map(|s| s.get_mass() as i64 * s.get_x())
+                .sum::<i64>(),
+            2
+        );
+        assert_eq!(centre_of_mass(&centred).unwrap(), Some([Fixed::ZERO; 3]));
+        assert_eq!(total_momentum(&centred).unwrap(), [0, 1, 0]);
+
+        // tracers carry no weight, whatever their mass
+        let mut traced = system.clone();
+        traced.push(
+            MotionState::new(3, 5 * PRECISION as u64, 0, 0, PRECISION, 0)
+                .with_kind(BodyKind::Tracer),
+        );
+        assert_eq!(
+            centre_of_mass(&traced).unwrap(),
+            centre_of_mass(&system).unwrap()
+        );
+        assert_eq!(
+            total_momentum(&traced).unwrap(),
+            total_momentum(&system).unwrap()
+        );
+
+        // anchors fix the frame, so only positions are moved
+        let mut anchored = system.clone();
+        anchored[0] = anchored[0].with_kind(BodyKind::Anchor);
+        let centred = centre_of_mass_frame(&anchored).unwrap();
+        assert_eq!(centre_of_mass(&centred).unwrap(), Some([Fixed::ZERO; 3]));
+        for (s, c) in anchored.iter().zip(&centred) {
+            assert_eq!(
+                (c.get_vel_x(), c.get_vel_y()),
+                (s.get_vel_x(), s.get_vel_y())
+            );
+        }
+
+        // nothing with mass leaves nothing to centre on
+        let ring = debris_ring(6)[1..].to_vec();
+        assert_eq!(centre_of_mass(&ring).unwrap(), None);
+        assert_same_system(&centre_of_mass_frame(&ring).unwrap(), &ring);
+
+        // and in three dimensions
+        let system = vec![
+            MotionState3D::new(
+                0,
+                PRECISION as u64,
+                [PRECISION, 0, PRECISION / 3],
+                [0, 7, 0],
+            ),
+            MotionState3D::new(1, 2 * PRECISION as u64, [0, PRECISION, 0], [5, 0, -3]),
+        ];
+        let centred = centre_of_mass_frame(&system).unwrap();
+        assert_eq!(centre_of_mass(&centred).unwrap(), Some([Fixed::ZERO; 3]));
+        assert!(total_momentum(&centred)
+            .unwrap()
+            .iter()
+            .all(|p| p.abs() < 3 * PRECISION as i128));
     }
 }